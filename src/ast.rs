@@ -0,0 +1,173 @@
+use std::fmt;
+
+use crate::{error::Span, lexer::Function};
+
+/// A parsed Econ expression. Produced by [`EconParser::parse_ast`](crate::parser::EconParser::parse_ast) and
+/// turned into an [`EconValue`](crate::value::EconValue) by [`EconEvaluator`](crate::evaluator::EconEvaluator).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Nil,
+    Num(f64),
+    Int(i64),
+    #[cfg(feature = "decimal")]
+    Dec(rust_decimal::Decimal),
+    Bool(bool),
+    Str(String),
+    /// `$name`, `$$name`, ... or `!name`
+    Var(Var),
+    /// `target.key` or `target[key]`
+    Access {
+        target: Box<Expr>,
+        key: Box<Expr>,
+        bracket: bool,
+    },
+    Object(Object),
+    Array(Vec<Expr>),
+    /// `( expr )`
+    Group(Box<Expr>),
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `condition ? then : otherwise`
+    Ternary {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    Call {
+        func: Function,
+        args: Vec<Expr>,
+    },
+    /// `$name(args)` or `!name(args)`, a call to a function bound with `let` or `fn`
+    Apply {
+        func: Var,
+        args: Vec<Expr>,
+    },
+    Lambda(Lambda),
+}
+
+/// A reference to a key. `depth` counts how many objects up to look (`$` is 0, `$$` is 1, ...) and is -1 for
+/// `!` which searches every enclosing object from the innermost outward.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Var {
+    pub depth: isize,
+    pub name: String,
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.depth < 0 {
+            write!(f, "!{}", self.name)
+        } else {
+            write!(f, "{}{}", "$".repeat(self.depth as usize + 1), self.name)
+        }
+    }
+}
+
+/// `x => body` or `|x, y| => body` as passed to functions like `filter`, `map`, `fold` and `sort`, or bound to
+/// a name with `let` or `fn`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lambda {
+    pub params: Vec<String>,
+    pub body: Box<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub items: Vec<Item>,
+}
+
+/// Objects hold their keys, local bindings and the constraint/error macros declared between them, in source order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Entry(Entry),
+    /// `let name = value` or `fn name(params) => body`. Visible to the object and everything nested in it but
+    /// left out of its value.
+    Let(Entry),
+    Constraint(Constraint),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: Expr,
+    pub value: Expr,
+}
+
+/// `@{type, param => condition, value}` or, with `is_error` set, `@!{type, param => condition, message}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    pub ty: String,
+    pub param: String,
+    pub condition: Expr,
+    pub value: Expr,
+    pub is_error: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// `-`
+    Neg,
+    /// `not` / `~`
+    Not,
+    /// `#`
+    Len,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    And,
+    Or,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    /// `\` joins with a newline
+    Concat,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "~=",
+            BinaryOp::Less => "<",
+            BinaryOp::Greater => ">",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Concat => "\\",
+        }
+    }
+}
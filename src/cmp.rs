@@ -0,0 +1,269 @@
+use std::{cmp::Ordering, hash::{Hash, Hasher}};
+
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
+use crate::{object::EconObj, value::EconValue};
+
+/// 2^63, the first `f64` past `i64::MAX`.
+const I64_END: f64 = 9223372036854775808.0;
+
+/// Equality is total so `EconValue` can be `Eq`, a `HashSet` member or a `BTreeMap` key. Numbers compare
+/// by value whatever their representation, so `1`, `1.0` and a `decimal` `1.00` are equal, and each pair of
+/// representations is compared exactly so equality stays transitive. `NaN` equals itself and `-0.0` equals
+/// `0.0`. Objects are equal when they hold the same keys with equal values, in any order.
+impl PartialEq for EconValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (EconValue::Str(a), EconValue::Str(b)) => a == b,
+            (EconValue::Obj(a), EconValue::Obj(b)) => a == b,
+            _ => self.cmp(other) == Ordering::Equal,
+        }
+    }
+}
+
+impl Eq for EconValue {}
+
+impl PartialOrd for EconValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// `nil < bool < number < string < array < object`, then by value within a type. `NaN` sorts after every
+/// other number, arrays compare element by element and objects by their entries sorted by key.
+impl Ord for EconValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (EconValue::Nil, EconValue::Nil) => Ordering::Equal,
+            (EconValue::Bool(a), EconValue::Bool(b)) => a.cmp(b),
+            (EconValue::Str(a), EconValue::Str(b)) => a.cmp(b),
+            (EconValue::Arr(a), EconValue::Arr(b)) => a.cmp(b),
+            (EconValue::Obj(a), EconValue::Obj(b)) => a.cmp(b),
+            (a, b) if a.rank() == 2 && b.rank() == 2 => number_cmp(a, b),
+            (a, b) => a.rank().cmp(&b.rank()),
+        }
+    }
+}
+
+/// Agrees with equality: a number hashes as its exact value written as a decimal with no trailing zeros,
+/// whatever its representation. Floats too precise or too large for that hash their bits, with every `NaN`
+/// hashing alike.
+impl Hash for EconValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            EconValue::Nil => {}
+            EconValue::Bool(b) => b.hash(state),
+            EconValue::Int(n) => hash_exact(*n as i128, 0, state),
+            EconValue::Num(n) => match float_exact(*n) {
+                Some((mantissa, scale)) => hash_exact(mantissa, scale, state),
+                None => {
+                    1u8.hash(state);
+                    if n.is_nan() { f64::NAN } else { *n }.to_bits().hash(state);
+                }
+            },
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => hash_exact(n.mantissa(), n.scale(), state),
+            EconValue::Str(s) => s.hash(state),
+            EconValue::Arr(a) => a.hash(state),
+            EconValue::Obj(o) => o.hash(state),
+        }
+    }
+}
+
+impl PartialEq for EconObj {
+    fn eq(&self, other: &Self) -> bool {
+        self.data.len() == other.data.len()
+            && self.data.iter().all(|(k, v)| other.data.get(k).is_some_and(|o| v == o))
+    }
+}
+
+impl Eq for EconObj {}
+
+impl PartialOrd for EconObj {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EconObj {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sorted().cmp(&other.sorted())
+    }
+}
+
+impl Hash for EconObj {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sorted().hash(state);
+    }
+}
+
+impl EconObj {
+    fn sorted(&self) -> Vec<(&String, &EconValue)> {
+        let mut entries: Vec<(&String, &EconValue)> = self.data.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+}
+
+impl EconValue {
+    /// Position of the type in the sort order.
+    fn rank(&self) -> u8 {
+        match self {
+            EconValue::Nil => 0,
+            EconValue::Bool(_) => 1,
+            EconValue::Num(_) | EconValue::Int(_) => 2,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(_) => 2,
+            EconValue::Str(_) => 3,
+            EconValue::Arr(_) => 4,
+            EconValue::Obj(_) => 5,
+        }
+    }
+}
+
+fn number_cmp(a: &EconValue, b: &EconValue) -> Ordering {
+    match (a, b) {
+        (EconValue::Int(x), EconValue::Int(y)) => x.cmp(y),
+        (EconValue::Int(x), EconValue::Num(y)) => int_float_cmp(*x, *y),
+        (EconValue::Num(x), EconValue::Int(y)) => int_float_cmp(*y, *x).reverse(),
+        (EconValue::Num(x), EconValue::Num(y)) => float_cmp(*x, *y),
+        #[cfg(feature = "decimal")]
+        (EconValue::Dec(x), EconValue::Dec(y)) => x.cmp(y),
+        #[cfg(feature = "decimal")]
+        (EconValue::Dec(x), EconValue::Int(y)) => x.cmp(&Decimal::from(*y)),
+        #[cfg(feature = "decimal")]
+        (EconValue::Int(x), EconValue::Dec(y)) => Decimal::from(*x).cmp(y),
+        #[cfg(feature = "decimal")]
+        (EconValue::Dec(x), EconValue::Num(y)) => dec_float_cmp(x, *y),
+        #[cfg(feature = "decimal")]
+        (EconValue::Num(x), EconValue::Dec(y)) => dec_float_cmp(y, *x).reverse(),
+        _ => a.rank().cmp(&b.rank()),
+    }
+}
+
+/// IEEE order except that `NaN` equals itself and is greater than everything else.
+fn float_cmp(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        _ => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// `i` against `f` without rounding `i` to the nearest `f64`.
+fn int_float_cmp(i: i64, f: f64) -> Ordering {
+    if f.is_nan() || f >= I64_END {
+        return Ordering::Less;
+    }
+    if f < -I64_END {
+        return Ordering::Greater;
+    }
+
+    let whole = f.trunc();
+    i.cmp(&(whole as i64)).then_with(|| float_cmp(whole, f))
+}
+
+/// `|f|` as `mantissa * 2^exponent`.
+fn float_parts(f: f64) -> (u64, i32) {
+    let bits = f.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i32;
+    let fraction = bits & ((1 << 52) - 1);
+    if exponent == 0 {
+        (fraction, -1074)
+    } else {
+        (fraction | (1 << 52), exponent - 1075)
+    }
+}
+
+/// `f` exactly as `mantissa / 10^scale` with a scale of at most 28, the most a `decimal` can have, if it fits.
+fn float_exact(f: f64) -> Option<(i128, u32)> {
+    if !f.is_finite() {
+        return None;
+    }
+
+    let (mut m, mut e) = float_parts(f);
+    if m == 0 {
+        return Some((0, 0));
+    }
+    while m % 2 == 0 && e < 0 {
+        m /= 2;
+        e += 1;
+    }
+
+    let mantissa = if e >= 0 {
+        if 64 - m.leading_zeros() as i32 + e > 126 {
+            return None;
+        }
+        (m as i128) << e
+    } else {
+        // m / 2^k is m * 5^k / 10^k, which no decimal equals when k > 28 as m is odd
+        let k = e.unsigned_abs();
+        if k > 28 {
+            return None;
+        }
+        m as i128 * 5i128.pow(k)
+    };
+
+    let mantissa = if f < 0.0 { -mantissa } else { mantissa };
+    Some((mantissa, e.min(0).unsigned_abs()))
+}
+
+/// Hash `mantissa / 10^scale` with trailing zeros removed, so equal numbers hash alike.
+fn hash_exact<H: Hasher>(mut mantissa: i128, mut scale: u32, state: &mut H) {
+    while scale > 0 && mantissa % 10 == 0 {
+        mantissa /= 10;
+        scale -= 1;
+    }
+    0u8.hash(state);
+    mantissa.hash(state);
+    scale.hash(state);
+}
+
+/// `d` against `f` without rounding either.
+#[cfg(feature = "decimal")]
+fn dec_float_cmp(d: &Decimal, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { Ordering::Less } else { Ordering::Greater };
+    }
+
+    let (fm, fe) = float_parts(f);
+    let d_sign = d.mantissa().signum();
+    let f_sign = if fm == 0 { 0 } else if f < 0.0 { -1 } else { 1 };
+    if d_sign != f_sign || d_sign == 0 {
+        return d_sign.cmp(&f_sign);
+    }
+
+    let magnitude = magnitude_cmp(d.mantissa().unsigned_abs(), d.scale(), fm, fe);
+    if d_sign < 0 {
+        magnitude.reverse()
+    } else {
+        magnitude
+    }
+}
+
+/// `n / 10^s` against `m * 2^e`, as `n` against `m * 5^s * 2^(e + s)`. `n` is below 2^96 and `m * 5^s` below
+/// 2^119, so whenever a shift could overflow the answer is already known.
+#[cfg(feature = "decimal")]
+fn magnitude_cmp(n: u128, s: u32, m: u64, e: i32) -> Ordering {
+    let rhs = m as u128 * 5u128.pow(s);
+    let t = e + s as i32;
+
+    if t >= 0 {
+        if 128 - rhs.leading_zeros() as i32 + t > 96 {
+            return Ordering::Less;
+        }
+        n.cmp(&(rhs << t))
+    } else {
+        let t = -t;
+        if 128 - n.leading_zeros() as i32 + t > 119 {
+            return Ordering::Greater;
+        }
+        (n << t).cmp(&rhs)
+    }
+}
@@ -0,0 +1,455 @@
+use std::{fmt, iter::Enumerate, slice};
+
+use serde::de::{
+    self, value::BorrowedStrDeserializer, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
+    SeqAccess, Unexpected, VariantAccess, Visitor,
+};
+
+use crate::{error::EconError, object::EconObj, value::EconValue};
+#[cfg(feature = "decimal")]
+use crate::ser::{decimal_form, DecimalForm};
+
+/// Deserialize a `T` straight from an [`EconValue`] without going through Json text.
+/// Strings are borrowed from `value` where `T` allows it.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, de::from_value};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+///     tags: Vec<String>,
+///     backup: Option<Box<Server>>,
+/// }
+///
+/// let obj = Econ::from(r#"{ host: "a.local", port: 80 * 100, tags: ["web"], backup: nil }"#);
+/// let server: Server = from_value(&obj).unwrap();
+/// assert_eq!(8000, server.port);
+/// assert!(server.backup.is_none());
+/// ```
+pub fn from_value<'de, T: de::Deserialize<'de>>(value: &'de EconValue) -> Result<T, EconError> {
+    T::deserialize(value)
+}
+
+impl de::Error for EconError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        EconError::Deserialize {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl EconValue {
+    fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            EconValue::Nil => Unexpected::Unit,
+            EconValue::Num(n) => Unexpected::Float(*n),
+            EconValue::Int(n) => Unexpected::Signed(*n),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(_) => Unexpected::Other("decimal"),
+            EconValue::Bool(b) => Unexpected::Bool(*b),
+            EconValue::Str(s) => Unexpected::Str(s),
+            EconValue::Arr(_) => Unexpected::Seq,
+            EconValue::Obj(_) => Unexpected::Map,
+        }
+    }
+
+    fn invalid_type(&self, exp: &dyn de::Expected) -> EconError {
+        de::Error::invalid_type(self.unexpected(), exp)
+    }
+}
+
+macro_rules! deserialize_int {
+    ($($method:ident => $visit:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+                match self {
+                    EconValue::Int(n) => match <$ty>::try_from(*n) {
+                        Ok(v) => visitor.$visit(v),
+                        Err(_) => Err(de::Error::invalid_value(self.unexpected(), &visitor)),
+                    },
+                    #[cfg(feature = "decimal")]
+                    EconValue::Dec(n) if n.fract().is_zero() => {
+                        match rust_decimal::prelude::ToPrimitive::to_i128(n).and_then(|v| <$ty>::try_from(v).ok()) {
+                            Some(v) => visitor.$visit(v),
+                            None => Err(de::Error::invalid_value(self.unexpected(), &visitor)),
+                        }
+                    }
+                    EconValue::Num(n) if n.fract() == 0.0 && *n >= <$ty>::MIN as f64 && *n <= <$ty>::MAX as f64 => {
+                        visitor.$visit(*n as $ty)
+                    }
+                    _ => Err(self.invalid_type(&visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for &'de EconValue {
+    type Error = EconError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Nil => visitor.visit_unit(),
+            EconValue::Num(n) if n.fract() == 0.0 && n.abs() < (1u64 << 53) as f64 => {
+                if *n >= 0.0 {
+                    visitor.visit_u64(*n as u64)
+                } else {
+                    visitor.visit_i64(*n as i64)
+                }
+            }
+            EconValue::Num(n) => visitor.visit_f64(*n),
+            EconValue::Int(n) if *n >= 0 => visitor.visit_u64(*n as u64),
+            EconValue::Int(n) => visitor.visit_i64(*n),
+            // Decimals a float can't carry exactly are handed over as text so `rust_decimal::Decimal` receives every digit
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => match decimal_form(n) {
+                DecimalForm::Int(i) if i >= 0 => visitor.visit_u64(i as u64),
+                DecimalForm::Int(i) => visitor.visit_i64(i),
+                DecimalForm::Float(f) => visitor.visit_f64(f),
+                DecimalForm::Text(digits) => visitor.visit_string(digits),
+            },
+            EconValue::Bool(b) => visitor.visit_bool(*b),
+            EconValue::Str(s) => visitor.visit_borrowed_str(s),
+            EconValue::Arr(a) => visit_arr(a, visitor),
+            EconValue::Obj(o) => visit_obj(o, visitor),
+        }
+    }
+
+    deserialize_int! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Num(n) => visitor.visit_f64(*n),
+            EconValue::Int(n) => visitor.visit_f64(*n as f64),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(_) => visitor.visit_f64(self.as_f64().unwrap_or(f64::NAN)),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Bool(b) => visitor.visit_bool(*b),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Str(s) => visitor.visit_borrowed_str(s),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Str(s) => visitor.visit_borrowed_bytes(s.as_bytes()),
+            EconValue::Arr(a) => visit_arr(a, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Nil => visitor.visit_unit(),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Arr(a) => visit_arr(a, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Obj(o) => visit_obj(o, visitor),
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        self.deserialize_map(visitor)
+    }
+
+    /// A unit variant is written as its name, `"Red"`. Every other variant is an object with the
+    /// variant name as its only key, `{ Rgb: [255, 0, 0] }`.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        match self {
+            EconValue::Str(s) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+            EconValue::Obj(o) if o.data.len() == 1 => {
+                let (variant, value) = o.data.iter().next().unwrap();
+                visitor.visit_enum(Variant { variant, value })
+            }
+            _ => Err(self.invalid_type(&visitor)),
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, EconError> for &'de EconValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_arr<'de, V: Visitor<'de>>(arr: &'de [EconValue], visitor: V) -> Result<V::Value, EconError> {
+    let mut access = ArrAccess {
+        iter: arr.iter().enumerate(),
+    };
+    let value = visitor.visit_seq(&mut access)?;
+
+    if access.iter.len() == 0 {
+        Ok(value)
+    } else {
+        Err(de::Error::invalid_length(arr.len(), &"fewer elements in array"))
+    }
+}
+
+fn visit_obj<'de, V: Visitor<'de>>(obj: &'de EconObj, visitor: V) -> Result<V::Value, EconError> {
+    visitor.visit_map(ObjAccess {
+        iter: obj.data.iter(),
+        value: None,
+    })
+}
+
+struct ArrAccess<'de> {
+    iter: Enumerate<slice::Iter<'de, EconValue>>,
+}
+
+impl<'de> SeqAccess<'de> for ArrAccess<'de> {
+    type Error = EconError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, EconError> {
+        match self.iter.next() {
+            Some((i, value)) => seed
+                .deserialize(value)
+                .map(Some)
+                .map_err(|e| e.at(&format!("[{}]", i))),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct ObjAccess<'de> {
+    iter: indexmap::map::Iter<'de, String, EconValue>,
+    value: Option<(&'de str, &'de EconValue)>,
+}
+
+impl<'de> MapAccess<'de> for ObjAccess<'de> {
+    type Error = EconError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, EconError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(KeyDeserializer(key))
+                    .map(Some)
+                    .map_err(|e| e.at(key))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, EconError> {
+        match self.value.take() {
+            Some((key, value)) => seed.deserialize(value).map_err(|e| e.at(key)),
+            None => Err(de::Error::custom("value requested before key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Object keys are always strings. Map keys of other types are parsed from them, so `{ 1: true }`
+/// deserializes into a `HashMap<u32, bool>`.
+struct KeyDeserializer<'de>(&'de str);
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(_) => Err(de::Error::invalid_type(Unexpected::Str(self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = EconError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, EconError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        visitor.visit_enum(BorrowedStrDeserializer::new(self.0))
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct Variant<'de> {
+    variant: &'de str,
+    value: &'de EconValue,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'de> {
+    type Error = EconError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), EconError> {
+        let variant = seed
+            .deserialize(BorrowedStrDeserializer::<EconError>::new(self.variant))
+            .map_err(|e| e.at(self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'de> {
+    type Error = EconError;
+
+    fn unit_variant(self) -> Result<(), EconError> {
+        de::Deserialize::deserialize(self.value).map_err(|e| e.at(self.variant))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, EconError> {
+        seed.deserialize(self.value).map_err(|e| e.at(self.variant))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, EconError> {
+        self.value.deserialize_seq(visitor).map_err(|e| e.at(self.variant))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, EconError> {
+        self.value.deserialize_map(visitor).map_err(|e| e.at(self.variant))
+    }
+}
@@ -1,6 +1,6 @@
-use std::{fs, path::PathBuf, str::FromStr};
+use std::{io::Read, path::Path};
 
-use crate::{lexer::EconLexer, parser::EconParser, value::EconValue};
+use crate::{error::EconError, lexer::EconLexer, options::EconOptions, parser::EconParser, value::EconValue};
 
 /// Parse Econ from strings or files. Access values directly or deserialize into rust structs.
 /// # Examples
@@ -46,10 +46,14 @@ use crate::{lexer::EconLexer, parser::EconParser, value::EconValue};
 pub struct Econ;
 
 impl Econ {
-    /// create an EconValue
+    /// create an EconValue from ```&str``` or file path. ```options``` is an [`EconOptions`] or a ```bool```, where
+    /// ```true``` traces every stage to stdout.
+    ///
+    /// ```src``` is read as a file if ```options.resolver``` finds one at that path and parsed as source otherwise, so a mistyped
+    /// path ends up parsed as Econ. Prefer ```from_str``` or ```from_path``` when you know which one you have.
     /// # Example
     /// ```rust
-    /// use econ_rs::econ::Econ;
+    /// use econ_rs::{econ::Econ, options::EconOptions};
     ///
     /// let obj = Econ::create(
     /// r#"
@@ -59,48 +63,140 @@ impl Econ {
     ///     c: 3
     /// }
     /// "#, true);
+    ///
+    /// let obj = Econ::create("{ a: 1, a: 2 }", EconOptions::new().allow_duplicate_keys(true)).unwrap();
+    /// assert_eq!(2f64, obj["a"].value::<f64>());
     /// ```
-    pub fn create(src: &str, debug: bool) -> Result<EconValue, String> {
-        match PathBuf::from_str(src) {
-            Ok(pb) => {
-                let file = fs::read_to_string(pb);
-                match file {
-                    Ok(f) => {
-                        let mut parser = EconParser::new(&f);
-                        let mut lexer = EconLexer::init(&f);
-                        let result = parser.parse(&mut lexer, debug);
-                        if debug {
-                            if let Err(e) = &result {
-                                eprintln!("{}", e);
-                            }
-                        }
-                        result
-                    }
-                    Err(_) => {
-                        let mut parser = EconParser::new(src);
-                        let mut lexer = EconLexer::init(src);
-                        let result = parser.parse(&mut lexer, debug);
-                        if debug {
-                            if let Err(e) = &result {
-                                eprintln!("{}", e);
-                            }
-                        }
-                        result
-                    }
-                }
-            }
-            Err(_) => {
-                let mut parser = EconParser::new(src);
-                let mut lexer = EconLexer::init(src);
-                let result = parser.parse(&mut lexer, debug);
-                if debug {
-                    if let Err(e) = &result {
-                        eprintln!("{}", e);
-                    }
-                }
-                result
-            }
+    pub fn create(src: &str, options: impl Into<EconOptions>) -> Result<EconValue, EconError> {
+        let options = options.into();
+        match Self::load(&options, src) {
+            Ok((file, loaded)) => Self::from_str(&file, loaded),
+            Err(_) => Self::from_str(src, options),
+        }
+    }
+
+    /// create an EconValue from Econ source. ```src``` is never treated as a path.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, options::EconOptions};
+    ///
+    /// let obj = Econ::from_str("{ a: 1, b: $a + 1 }", false).unwrap();
+    /// assert_eq!(2f64, obj["b"].value::<f64>());
+    ///
+    /// let err = Econ::from_str("{ a: 1 b: 2 }", EconOptions::new().source_name("inline.econ")).unwrap_err();
+    /// assert_eq!(Some("inline.econ"), err.file());
+    /// ```
+    pub fn from_str(src: &str, options: impl Into<EconOptions>) -> Result<EconValue, EconError> {
+        let options = options.into();
+        let mut parser = EconParser::with_options(src, options.clone());
+        let mut lexer = EconLexer::with_options(src, options);
+        parser.parse(&mut lexer)
+    }
+
+    /// create an EconValue from the file at ```path```, read through ```options.resolver```. A file that can't be read is an ```EconError::Io```
+    /// naming the path, and every other error names the file unless ```options``` already sets a ```source_name```.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, error::ErrorKind};
+    ///
+    /// let err = Econ::from_path("missing.econ", false).unwrap_err();
+    /// assert_eq!(ErrorKind::Io, err.kind());
+    /// assert_eq!(Some("missing.econ"), err.file());
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>, options: impl Into<EconOptions>) -> Result<EconValue, EconError> {
+        let path = path.as_ref().display().to_string();
+        let (src, options) = Self::load(&options.into(), &path)?;
+        Self::from_str(&src, options)
+    }
+
+    /// create an EconValue from everything ```reader``` yields. Errors are named after ```options.source_name```
+    /// when set.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, options::EconOptions};
+    ///
+    /// let obj = Econ::from_reader("{ a: [1, 2, 3] }".as_bytes(), EconOptions::new().source_name("stdin")).unwrap();
+    /// assert_eq!(3f64, obj["a"][2].value::<f64>());
+    /// ```
+    pub fn from_reader<R: Read>(mut reader: R, options: impl Into<EconOptions>) -> Result<EconValue, EconError> {
+        let options = options.into();
+        let mut src = String::new();
+
+        if let Err(e) = reader.read_to_string(&mut src) {
+            let name = options.source_name.as_deref().unwrap_or("<reader>");
+            return Err(Self::io_error(name, e));
         }
+
+        Self::from_str(&src, options)
+    }
+
+    /// Read `path` through `options.resolver`. The options returned name errors after `path` and start the
+    /// import and include chains with the file's id so cycles back to it are caught.
+    fn load(options: &EconOptions, path: &str) -> Result<(String, EconOptions), EconError> {
+        let (id, src) = options.resolver.resolve(Path::new(""), path)?;
+        let mut options = Self::named(options.clone(), path);
+        options.importing = vec!(id.clone());
+        options.including = vec!(id);
+        Ok((src, options))
+    }
+
+    fn named(options: EconOptions, path: &str) -> EconOptions {
+        if options.source_name.is_some() {
+            options
+        } else {
+            options.source_name(path)
+        }
+    }
+
+    fn io_error(path: &str, e: std::io::Error) -> EconError {
+        EconError::Io {
+            path: path.to_string(),
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+
+    /// Like ```create``` but a reference to a key that doesn't exist is an ```EconError::Reference``` naming the
+    /// objects searched instead of silently becoming ```nil```. Same as passing ```EconOptions::new().strict_refs(true)```.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let err = Econ::create_strict("{ name: 1, b: $nmae }", false).unwrap_err();
+    /// assert_eq!("Unresolved reference '$nmae', searched root. Did you mean '$name'?", err.message());
+    /// ```
+    pub fn create_strict(src: &str, debug: bool) -> Result<EconValue, EconError> {
+        Self::create(src, EconOptions::from(debug).strict_refs(true))
+    }
+
+    /// create an EconValue from ```&str``` or file path without stopping at the first error. Every lexing, parsing
+    /// and error macro failure is collected and returned alongside whatever could still be evaluated. ```src``` and
+    /// ```options``` are handled as in ```create```.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let (obj, errors) = Econ::create_recovering(
+    /// r#"
+    /// {
+    ///     a: 1 + true,
+    ///     b: 2,
+    ///     c: [1, 2 3]
+    /// }
+    /// "#, false);
+    /// assert_eq!(2, errors.len());
+    /// assert_eq!(2f64, obj["b"].value::<f64>());
+    /// ```
+    pub fn create_recovering(src: &str, options: impl Into<EconOptions>) -> (EconValue, Vec<EconError>) {
+        let options = options.into();
+        let (src, options) = match Self::load(&options, src) {
+            Ok(loaded) => loaded,
+            Err(_) => (src.to_string(), options),
+        };
+
+        let mut parser = EconParser::with_options(&src, options.clone());
+        let mut lexer = EconLexer::with_options(&src, options);
+        parser.parse_recovering(&mut lexer)
     }
 
     /// create an EconValue from ```&str``` or file path. Does not include any debug info. Prints error message and returns ```EconValue::Nil``` on fail.
@@ -123,48 +219,17 @@ impl Econ {
     /// let obj = Econ::from("path/file.econ");
     /// ```
     pub fn from(src: &str) -> EconValue {
-        match PathBuf::try_from(src) {
-            Ok(pb) => {
-                match fs::read_to_string(pb) {
-                    Ok(s) => {
-                        match Self::create(&s, false) {
-                            Ok(v) => {
-                                v
-                            }
-                            Err(m) => {
-                                eprintln!("{}", m);
-                                EconValue::Nil
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        match Self::create(src, false) {
-                            Ok(v) => {
-                                v
-                            }
-                            Err(m) => {
-                                eprintln!("{}", m);
-                                EconValue::Nil
-                            }
-                        }
-                    }
-                }
-            }
-            Err(_) => {
-                match Self::create(src, false) {
-                    Ok(v) => {
-                        v
-                    }
-                    Err(m) => {
-                        eprintln!("{}", m);
-                        EconValue::Nil
-                    }
-                }
+        match Self::create(src, false) {
+            Ok(v) => v,
+            Err(m) => {
+                eprintln!("{}", m);
+                EconValue::Nil
             }
         }
     }
 
-    /// Deserialize Econ into a struct. Econ is a superset of Json so it utilizes serde to deserialize to struct.
+    /// Deserialize Econ into a struct. The value is walked directly by serde, see [`from_value`](crate::de::from_value).
+    /// A failure is an ```EconError::Deserialize``` carrying the path of the key that didn't fit.
     /// # Examples
     /// ```rust
     /// use econ_rs::econ::Econ;
@@ -185,8 +250,7 @@ impl Econ {
     /// }
     /// "#);
     /// ```
-    pub fn to_struct<T: for<'a> serde::de::Deserialize<'a>>(obj: &EconValue) -> Result<T, String> {
-        let result: Result<T, serde_json::Error> = serde_json::from_str(format!("{}", obj).as_str());
-        result.map_err(|e| e.to_string())
+    pub fn to_struct<T: for<'a> serde::de::Deserialize<'a>>(obj: &EconValue) -> Result<T, EconError> {
+        T::deserialize(obj)
     }
 }
@@ -0,0 +1,317 @@
+use std::{fmt, io, sync::Arc};
+
+use crate::lexer::Token;
+
+/// Machine readable category of an [`EconError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Lex,
+    Parse,
+    Type,
+    Reference,
+    Constraint,
+    Io,
+    Serialize,
+    Deserialize,
+    Path,
+}
+
+/// Location of a token or expression in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, start: usize, end: usize) -> Self {
+        Self {
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+}
+
+/// Everything known about a failure that happened while lexing, parsing or evaluating source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub token: Option<Box<Token>>,
+    pub source: Option<Arc<str>>,
+    /// Name of the file the source came from, if it came from one.
+    pub file: Option<Arc<str>>,
+}
+
+impl Diagnostic {
+    pub fn new(message: String, span: Span) -> Self {
+        Self {
+            message,
+            span,
+            token: None,
+            source: None,
+            file: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: Token) -> Self {
+        self.token = Some(Box::new(token));
+        self
+    }
+
+    pub fn with_source(mut self, source: Arc<str>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn with_file(mut self, file: Arc<str>) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    pub(crate) fn in_file(mut self, file: Option<Arc<str>>) -> Self {
+        self.file = file;
+        self
+    }
+
+    fn render(&self, f: &mut fmt::Formatter, stage: &str) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file)?;
+        }
+
+        writeln!(
+            f,
+            "Line [{:04}:{:03}] {} -> \"{}\"",
+            self.span.line, self.span.column, stage, self.message
+        )?;
+
+        if let Some(source) = &self.source {
+            let current_line = self.span.line;
+            let mut offset = 0;
+
+            for (line_num, raw) in source.split_inclusive('\n').enumerate() {
+                let line_start = offset;
+                offset += raw.len();
+                let line = raw.trim_end_matches(['\n', '\r']);
+
+                if line_num + 1 == current_line || line_num == current_line + 1 {
+                    writeln!(f, "[{:04}]   {}", line_num, line)?;
+                } else if line_num == current_line {
+                    writeln!(f, "-> [{:04}]{}", line_num, line)?;
+                    writeln!(f, "{}", Self::underline(line, line_start, self.span))?;
+                } else if line_num > current_line {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marker line placing `^` under the part of `line` covered by `span`. Tabs are mirrored so the
+    /// markers stay aligned with the source line printed above.
+    fn underline(line: &str, line_start: usize, span: Span) -> String {
+        let mut result = String::from("         ");
+        let mut marked = false;
+
+        for (i, c) in line.char_indices() {
+            let at = line_start + i;
+            if at >= span.end && marked {
+                break;
+            }
+            if at >= span.start {
+                result.push('^');
+                marked = true;
+            } else if c == '\t' {
+                result.push('\t');
+            } else {
+                result.push(' ');
+            }
+        }
+
+        if !marked {
+            result.push('^');
+        }
+
+        result
+    }
+}
+
+/// Error returned by every fallible Econ entry point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EconError {
+    Lex(Diagnostic),
+    Parse(Diagnostic),
+    Type(Diagnostic),
+    Reference(Diagnostic),
+    Constraint(Diagnostic),
+    Io {
+        path: String,
+        kind: io::ErrorKind,
+        message: String,
+    },
+    Serialize {
+        path: String,
+        message: String,
+    },
+    Deserialize {
+        path: String,
+        message: String,
+    },
+    /// A [`pointer`](crate::value::EconValue::pointer) or [`path`](crate::value::EconValue::path) lookup, or
+    /// an edit like [`insert`](crate::value::EconValue::insert), that failed. `path` runs up to and including
+    /// the segment that couldn't be resolved.
+    Path {
+        path: String,
+        message: String,
+    },
+}
+
+impl EconError {
+    /// Build the variant matching `kind` from a diagnostic.
+    /// `ErrorKind::Io`, `ErrorKind::Serialize`, `ErrorKind::Deserialize` and `ErrorKind::Path` carry no source location and are built directly.
+    pub fn from_diagnostic(kind: ErrorKind, diagnostic: Diagnostic) -> Self {
+        match kind {
+            ErrorKind::Lex => EconError::Lex(diagnostic),
+            ErrorKind::Parse => EconError::Parse(diagnostic),
+            ErrorKind::Type => EconError::Type(diagnostic),
+            ErrorKind::Reference => EconError::Reference(diagnostic),
+            ErrorKind::Constraint => EconError::Constraint(diagnostic),
+            ErrorKind::Io => EconError::Io {
+                path: String::new(),
+                kind: io::ErrorKind::Other,
+                message: diagnostic.message,
+            },
+            ErrorKind::Serialize => EconError::Serialize {
+                path: String::new(),
+                message: diagnostic.message,
+            },
+            ErrorKind::Deserialize => EconError::Deserialize {
+                path: String::new(),
+                message: diagnostic.message,
+            },
+            ErrorKind::Path => EconError::Path {
+                path: String::new(),
+                message: diagnostic.message,
+            },
+        }
+    }
+
+    /// Put `segment`, a key or an `[index]`, in front of the path of a serialize or deserialize error.
+    pub(crate) fn at(self, segment: &str) -> Self {
+        let join = |path: String| {
+            if path.is_empty() || path.starts_with('[') {
+                format!("{}{}", segment, path)
+            } else {
+                format!("{}.{}", segment, path)
+            }
+        };
+
+        match self {
+            EconError::Serialize { path, message } => EconError::Serialize { path: join(path), message },
+            EconError::Deserialize { path, message } => EconError::Deserialize { path: join(path), message },
+            e => e,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            EconError::Lex(_) => ErrorKind::Lex,
+            EconError::Parse(_) => ErrorKind::Parse,
+            EconError::Type(_) => ErrorKind::Type,
+            EconError::Reference(_) => ErrorKind::Reference,
+            EconError::Constraint(_) => ErrorKind::Constraint,
+            EconError::Io { .. } => ErrorKind::Io,
+            EconError::Serialize { .. } => ErrorKind::Serialize,
+            EconError::Deserialize { .. } => ErrorKind::Deserialize,
+            EconError::Path { .. } => ErrorKind::Path,
+        }
+    }
+
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            EconError::Lex(d)
+            | EconError::Parse(d)
+            | EconError::Type(d)
+            | EconError::Reference(d)
+            | EconError::Constraint(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            EconError::Io { message, .. }
+            | EconError::Serialize { message, .. }
+            | EconError::Deserialize { message, .. }
+            | EconError::Path { message, .. } => message,
+            _ => self.diagnostic().map(|d| d.message.as_str()).unwrap_or_default(),
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.diagnostic().map(|d| d.span)
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.span().map(|s| s.line)
+    }
+
+    pub fn column(&self) -> Option<usize> {
+        self.span().map(|s| s.column)
+    }
+
+    /// Name of the file the error came from. For `EconError::Io` this is the path that couldn't be read.
+    pub fn file(&self) -> Option<&str> {
+        match self {
+            EconError::Io { path, .. } => Some(path),
+            _ => self.diagnostic().and_then(|d| d.file.as_deref()),
+        }
+    }
+
+    pub fn token(&self) -> Option<&Token> {
+        self.diagnostic().and_then(|d| d.token.as_deref())
+    }
+}
+
+impl fmt::Display for EconError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EconError::Lex(d) => d.render(f, "Error Lexing"),
+            EconError::Parse(d) => d.render(f, "Error Parsing"),
+            EconError::Type(d) => d.render(f, "Type Error"),
+            EconError::Reference(d) => d.render(f, "Reference Error"),
+            EconError::Constraint(d) => d.render(f, "Constraint Error"),
+            EconError::Io { path, message, .. } => {
+                write!(f, "Error Reading \"{}\" -> \"{}\"", path, message)
+            }
+            EconError::Serialize { path, message } => {
+                if path.is_empty() {
+                    write!(f, "Error Serializing -> \"{}\"", message)
+                } else {
+                    write!(f, "Error Serializing at {} -> \"{}\"", path, message)
+                }
+            }
+            EconError::Deserialize { path, message } => {
+                if path.is_empty() {
+                    write!(f, "Error Deserializing -> \"{}\"", message)
+                } else {
+                    write!(f, "Error Deserializing at {} -> \"{}\"", path, message)
+                }
+            }
+            EconError::Path { path, message } => {
+                if path.is_empty() {
+                    write!(f, "Error Resolving -> \"{}\"", message)
+                } else {
+                    write!(f, "Error Resolving {} -> \"{}\"", path, message)
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for EconError {}
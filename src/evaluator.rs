@@ -0,0 +1,1196 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{
+    ast::{BinaryOp, Constraint, Entry, Expr, ExprKind, Item, Lambda, Object, UnaryOp, Var},
+    econ::Econ,
+    error::{Diagnostic, EconError, ErrorKind, Span},
+    import,
+    lexer::Function,
+    object::EconObj,
+    options::EconOptions,
+    value::EconValue,
+};
+
+/// How many entries may be evaluated ahead of their turn at once when `max_depth` is unset.
+const MAX_FORCED: usize = 256;
+
+/// An object being evaluated.
+struct Scope<'a> {
+    values: HashMap<String, EconValue>,
+    constraints: HashMap<&'a str, Vec<(usize, &'a Constraint)>>,
+    /// Functions bound with `let` or `fn`, by name.
+    functions: HashMap<&'a str, &'a Lambda>,
+    /// Entries with a literal key that have not been evaluated yet, by key.
+    pending: HashMap<&'a str, (usize, &'a Entry)>,
+    /// Entries evaluated ahead of their turn because something referenced them. `None` if that failed.
+    done: HashMap<usize, Option<(String, EconValue)>>,
+    /// Index of the entry being evaluated. Only constraints declared before it apply.
+    position: usize,
+    /// Number of call frames open when the object was entered. Only those are visible to its entries.
+    frames: usize,
+}
+
+impl<'a> Scope<'a> {
+    fn new(items: &'a [Item], frames: usize) -> Self {
+        let mut scope = Self {
+            values: HashMap::new(),
+            constraints: HashMap::new(),
+            functions: HashMap::new(),
+            pending: HashMap::new(),
+            done: HashMap::new(),
+            position: 0,
+            frames
+        };
+
+        for (index, item) in items.iter().enumerate() {
+            match item {
+                Item::Constraint(c) => {
+                    scope.constraints.entry(c.ty.as_str()).or_default().push((index, c));
+                }
+                Item::Entry(entry) => {
+                    if let ExprKind::Str(key) = &entry.key.kind {
+                        scope.pending.entry(key.as_str()).or_insert((index, entry));
+                    }
+                }
+                Item::Let(entry) => match (&entry.key.kind, &entry.value.kind) {
+                    (ExprKind::Str(name), ExprKind::Lambda(l)) => {
+                        scope.functions.entry(name.as_str()).or_insert(l);
+                    }
+                    (ExprKind::Str(name), _) => {
+                        scope.pending.entry(name.as_str()).or_insert((index, entry));
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        scope
+    }
+}
+
+/// References bound by a lambda or constraint while it runs. They are looked up before the keys of the object
+/// at `depth` and never written to it.
+struct Frame<'a> {
+    depth: usize,
+    values: HashMap<&'a str, EconValue>,
+}
+
+/// A lambda passed to a function, or one bound with `let` or `fn` along with the depth of the object it was
+/// bound in. Bound functions run in that object, so they see its keys wherever they are called from.
+#[derive(Clone, Copy)]
+struct Func<'a> {
+    lambda: &'a Lambda,
+    depth: Option<usize>,
+}
+
+/// Walks an [`Expr`] produced by [`EconParser::parse_ast`](crate::parser::EconParser::parse_ast) and computes its value,
+/// resolving references and applying constraint/error macros along the way.
+///
+/// Keys within an object are evaluated on demand, so a key may reference one declared after it. Referencing
+/// a key that is still being evaluated is a cycle and reported as a reference error.
+pub struct EconEvaluator<'a> {
+    source: Arc<str>,
+    file: Option<Arc<str>>,
+    scopes: Vec<Scope<'a>>,
+    frames: Vec<Frame<'a>>,
+    /// Keys being evaluated, outermost first, with the depth of the object they belong to. Depths never decrease.
+    path: Vec<(usize, String)>,
+    in_constraint: bool,
+    /// Calls to bound functions in progress.
+    calls: usize,
+    /// Entries being evaluated ahead of their turn.
+    forced: usize,
+    strict: bool,
+    allow_duplicate_keys: bool,
+    /// Native functions, host variables, environment access and imports. Handed on to imported files.
+    options: EconOptions,
+    recover: bool,
+    errors: Vec<EconError>
+}
+
+impl<'a> EconEvaluator<'a> {
+    /// `source` is only used to render the offending lines in error messages.
+    pub fn new(source: impl Into<Arc<str>>) -> Self {
+        Self::with_options(source, &EconOptions::new())
+    }
+
+    /// Uses `strict_refs`, `allow_duplicate_keys`, `max_depth`, `max_call_depth`, `source_name`, `native_fns`,
+    /// `vars`, `env`, `includes`, `import_cache` and `resolver` from `options`.
+    pub fn with_options(source: impl Into<Arc<str>>, options: &EconOptions) -> Self {
+        Self {
+            source: source.into(),
+            file: options.source_name.clone(),
+            scopes: vec!(),
+            frames: vec!(),
+            path: vec!(),
+            in_constraint: false,
+            calls: 0,
+            forced: 0,
+            strict: options.strict_refs,
+            allow_duplicate_keys: options.allow_duplicate_keys,
+            options: options.clone(),
+            recover: false,
+            errors: vec!()
+        }
+    }
+
+    pub fn evaluate(&mut self, expr: &'a Expr) -> Result<EconValue, EconError> {
+        self.scopes.clear();
+        self.frames.clear();
+        self.path.clear();
+        self.in_constraint = false;
+        self.calls = 0;
+        self.forced = 0;
+
+        if let ExprKind::Object(_) = expr.kind {
+            self.value(expr)
+        } else {
+            self.scopes.push(Scope::new(&[], 0));
+            let result = self.value(expr);
+            self.scopes.pop();
+            result
+        }
+    }
+
+    /// Like `evaluate` but a failing key or array element is recorded and left out instead of
+    /// ending the evaluation.
+    pub fn evaluate_recovering(&mut self, expr: &'a Expr) -> (EconValue, Vec<EconError>) {
+        self.recover = true;
+        self.errors.clear();
+
+        let value = match self.evaluate(expr) {
+            Ok(value) => value,
+            Err(e) => {
+                self.errors.push(e);
+                EconValue::Nil
+            }
+        };
+
+        self.recover = false;
+        (value, std::mem::take(&mut self.errors))
+    }
+
+    fn error(&self, kind: ErrorKind, span: Span, msg: String) -> EconError {
+        let diagnostic = Diagnostic::new(msg, span)
+            .with_source(self.source.clone())
+            .in_file(self.file.clone());
+        EconError::from_diagnostic(kind, diagnostic)
+    }
+
+    fn fail<T>(&self, kind: ErrorKind, span: Span, msg: String) -> Result<T, EconError> {
+        Err(self.error(kind, span, msg))
+    }
+
+    /// In recovering mode records `e` and lets the caller carry on. Otherwise `e` is returned as is.
+    fn recover_from(&mut self, e: EconError) -> Result<(), EconError> {
+        if !self.recover {
+            return Err(e);
+        }
+        self.in_constraint = false;
+        self.errors.push(e);
+        Ok(())
+    }
+
+    /// Evaluate an expression in value position, which is where constraints apply.
+    fn value(&mut self, expr: &'a Expr) -> Result<EconValue, EconError> {
+        let val = self.eval(expr)?;
+        self.check_val_with_constraint(val, expr.span)
+    }
+
+    fn eval(&mut self, expr: &'a Expr) -> Result<EconValue, EconError> {
+        match &expr.kind {
+            ExprKind::Nil => Ok(EconValue::Nil),
+            ExprKind::Num(n) => Ok(EconValue::Num(*n)),
+            ExprKind::Int(n) => Ok(EconValue::Int(*n)),
+            #[cfg(feature = "decimal")]
+            ExprKind::Dec(n) => Ok(EconValue::Dec(*n)),
+            ExprKind::Bool(b) => Ok(EconValue::Bool(*b)),
+            ExprKind::Str(s) => Ok(EconValue::Str(s.clone())),
+            ExprKind::Var(v) => self.lookup(v, expr.span),
+            ExprKind::Access { target, key, bracket } => {
+                let target = self.eval(target)?;
+                let key = if *bracket {
+                    self.value(key)?
+                } else {
+                    self.eval(key)?
+                };
+                self.access(target, key, expr.span)
+            }
+            ExprKind::Object(o) => self.object(o),
+            ExprKind::Array(items) => {
+                let mut result = vec!();
+                for item in items {
+                    match self.value(item) {
+                        Ok(v) => result.push(v),
+                        Err(e) => self.recover_from(e)?,
+                    }
+                }
+                Ok(EconValue::Arr(result))
+            }
+            ExprKind::Group(inner) => self.value(inner),
+            ExprKind::Unary { op, expr: right } => {
+                let right = self.eval(right)?;
+                self.unary(*op, right, expr.span)
+            }
+            ExprKind::Binary { op, left, right } => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                self.binary(*op, left, right, expr.span)
+            }
+            ExprKind::Ternary { condition, then, otherwise } => {
+                match self.eval(condition)? {
+                    EconValue::Bool(true) => self.eval(then),
+                    EconValue::Bool(false) => self.eval(otherwise),
+                    v => self.fail(ErrorKind::Type, expr.span, format!("Invalid ternary expected bool got: {}", v))
+                }
+            }
+            ExprKind::Call { func, args } => self.call(func, args, expr.span),
+            ExprKind::Apply { func, args } => self.apply(func, args, expr.span),
+            ExprKind::Lambda(_) => {
+                self.fail(ErrorKind::Type, expr.span, "Lambdas can only be passed to functions.".to_string())
+            }
+        }
+    }
+
+    fn lookup(&mut self, v: &Var, span: Span) -> Result<EconValue, EconError> {
+        let depth = self.scopes.len() as isize - 1;
+
+        if v.depth >= 0 {
+            if depth - v.depth < 0 {
+                return self.unresolved(v, &[], span);
+            }
+            match self.resolve((depth - v.depth) as usize, &v.name, span)? {
+                Some(value) => Ok(value),
+                None => self.unresolved(v, &[(depth - v.depth) as usize], span)
+            }
+        } else {
+            let mut found = false;
+            for scope in (0..self.scopes.len()).rev() {
+                match self.resolve(scope, &v.name, span)? {
+                    Some(EconValue::Nil) => found = true,
+                    Some(value) => return Ok(value),
+                    None => continue
+                }
+            }
+
+            if found {
+                return Ok(EconValue::Nil);
+            }
+
+            // Host variables sit outside the root object, so only a name no object has reaches them
+            if let Some(value) = self.options.vars.get(&v.name) {
+                return Ok(value.clone());
+            }
+
+            let searched: Vec<usize> = (0..self.scopes.len()).rev().collect();
+            self.unresolved(v, &searched, span)
+        }
+    }
+
+    /// The function bound to `v` with `let` or `fn`. Like keys, `$` looks in one object and `!` searches outward.
+    fn function(&self, v: &Var) -> Option<Func<'a>> {
+        let depths: Vec<usize> = if v.depth >= 0 {
+            let depth = self.scopes.len() as isize - 1 - v.depth;
+            (depth >= 0).then_some(depth as usize).into_iter().collect()
+        } else {
+            (0..self.scopes.len()).rev().collect()
+        };
+
+        depths.into_iter().find_map(|depth| {
+            let lambda = self.scopes[depth].functions.get(v.name.as_str())?;
+            Some(Func { lambda, depth: Some(depth) })
+        })
+    }
+
+    /// `nil`, or in strict mode an error naming the objects at the `searched` depths and the closest key in them.
+    fn unresolved(&self, v: &Var, searched: &[usize], span: Span) -> Result<EconValue, EconError> {
+        if self.function(v).is_some() {
+            return self.fail(ErrorKind::Type, span, format!("'{}' is a function, call it with '{}(...)'.", v, v));
+        }
+        if !self.strict {
+            return Ok(EconValue::Nil);
+        }
+
+        if searched.is_empty() {
+            return self.fail(ErrorKind::Reference, span, format!(
+                "Unresolved reference '{}': there are only {} enclosing object(s).", v, self.scopes.len()
+            ));
+        }
+
+        let scopes: Vec<String> = searched.iter().map(|depth| self.scope_name(*depth)).collect();
+        let mut msg = format!("Unresolved reference '{}', searched {}.", v, scopes.join(", "));
+
+        let suggestion = searched.iter()
+            .flat_map(|depth| {
+                let scope = &self.scopes[*depth];
+                scope.values.keys().map(|k| k.as_str()).chain(scope.pending.keys().copied())
+            })
+            .map(|key| (edit_distance(&v.name, key), key))
+            .filter(|(distance, _)| *distance <= (v.name.chars().count() / 3).max(2) && *distance < v.name.chars().count())
+            .min();
+
+        if let Some((_, key)) = suggestion {
+            msg.push_str(&format!(" Did you mean '{}'?", Var { depth: v.depth, name: key.to_string() }));
+        }
+
+        self.fail(ErrorKind::Reference, span, msg)
+    }
+
+    /// Dotted path of keys leading to the object at `depth`, e.g. `root.a.b`.
+    fn scope_name(&self, depth: usize) -> String {
+        let mut name = String::from("root");
+        for d in 0..depth {
+            match self.path.iter().rev().find(|(key_depth, _)| *key_depth == d) {
+                Some((_, key)) => name.push_str(&format!(".{}", key)),
+                None => name.push_str(".?"),
+            }
+        }
+        name
+    }
+
+    /// Value of `name` in the object at `depth`, evaluating its entry first if that hasn't happened yet. References
+    /// bound by a call running in that object come first.
+    fn resolve(&mut self, depth: usize, name: &str, span: Span) -> Result<Option<EconValue>, EconError> {
+        let bound = self.frames.iter().rev().filter(|frame| frame.depth == depth).find_map(|frame| frame.values.get(name));
+        if let Some(value) = bound {
+            return Ok(Some(value.clone()));
+        }
+
+        if let Some(value) = self.scopes[depth].values.get(name) {
+            return Ok(Some(value.clone()));
+        }
+
+        if let Some(&(index, entry)) = self.scopes[depth].pending.get(name) {
+            return self.force(depth, index, entry, span).map(Some);
+        }
+
+        if let Some(start) = self.path.iter().position(|(d, key)| *d == depth && key == name) {
+            let mut cycle: Vec<&str> = self.path[start..].iter().map(|(_, key)| key.as_str()).collect();
+            cycle.push(name);
+            return self.fail(ErrorKind::Reference, span, format!("Cycle detected: {}.", cycle.join(" -> ")));
+        }
+
+        Ok(None)
+    }
+
+    /// Evaluate the entry at `index` of the object at `depth` ahead of its turn, in the object as it was at
+    /// `depth` so the entry sees only what it would have in source order. At most `max_depth` entries, 256 if
+    /// unset, may be forced at once.
+    fn force(&mut self, depth: usize, index: usize, entry: &'a Entry, span: Span) -> Result<EconValue, EconError> {
+        let max = self.options.max_depth.unwrap_or(MAX_FORCED);
+        let result = if self.forced >= max {
+            self.fail(ErrorKind::Reference, span, format!("References nest deeper than the maximum of {}.", max))
+        } else {
+            let position = self.scopes[depth].position;
+            let in_constraint = std::mem::replace(&mut self.in_constraint, false);
+            self.forced += 1;
+
+            let result = self.within(depth, |this| this.block_entry(index, entry));
+
+            self.forced -= 1;
+            self.in_constraint = in_constraint;
+            self.scopes[depth].position = position;
+            result
+        };
+
+        match result {
+            Ok((key, value)) => {
+                self.scopes[depth].done.insert(index, Some((key, value.clone())));
+                Ok(value)
+            }
+            Err(e) => {
+                self.scopes[depth].done.insert(index, None);
+                Err(e)
+            }
+        }
+    }
+
+    fn access(&self, target: EconValue, key: EconValue, span: Span) -> Result<EconValue, EconError> {
+        match (&target, key) {
+            (EconValue::Obj(o), EconValue::Str(s)) => {
+                Ok(o.data.get(&s).cloned().unwrap_or(EconValue::Nil))
+            }
+            (EconValue::Arr(a), EconValue::Num(n)) => {
+                if n < 0.0 {
+                    Ok(EconValue::Nil)
+                } else {
+                    Ok(a.get(n as usize).cloned().unwrap_or(EconValue::Nil))
+                }
+            }
+            (EconValue::Arr(a), EconValue::Int(n)) => {
+                Ok(usize::try_from(n).ok().and_then(|i| a.get(i).cloned()).unwrap_or(EconValue::Nil))
+            }
+            (EconValue::Str(s), EconValue::Int(n)) => {
+                Ok(usize::try_from(n)
+                    .ok()
+                    .and_then(|i| s.chars().nth(i))
+                    .map(|c| EconValue::Str(c.to_string()))
+                    .unwrap_or(EconValue::Nil))
+            }
+            (EconValue::Str(s), EconValue::Num(n)) => {
+                if n < 0.0 {
+                    Ok(EconValue::Nil)
+                } else {
+                    Ok(s.chars().nth(n as usize).map(|c| EconValue::Str(c.to_string())).unwrap_or(EconValue::Nil))
+                }
+            }
+            (EconValue::Obj(_), _) => self.fail(ErrorKind::Type, span, "Expect key after selecter.".to_string()),
+            _ => self.fail(ErrorKind::Type, span, "Expect index after selecter.".to_string()),
+        }
+    }
+
+    fn object(&mut self, o: &'a Object) -> Result<EconValue, EconError> {
+        self.scopes.push(Scope::new(&o.items, self.frames.len()));
+        let result = self.block(o);
+        self.scopes.pop();
+        result
+    }
+
+    fn block(&mut self, o: &'a Object) -> Result<EconValue, EconError> {
+        let mut result = EconObj::new();
+
+        for (index, item) in o.items.iter().enumerate() {
+            // `let` values are evaluated in turn like keys but left out, `let` and `fn` functions only run when called
+            let (entry, emit) = match item {
+                Item::Entry(entry) => (entry, true),
+                Item::Let(entry) if !matches!(entry.value.kind, ExprKind::Lambda(_)) => (entry, false),
+                _ => continue,
+            };
+
+            let done = self.scopes.last_mut().and_then(|scope| scope.done.remove(&index));
+
+            let (key, value) = match done {
+                Some(Some(key_val)) => key_val,
+                // already reported through whatever referenced it
+                Some(None) => continue,
+                None => match self.block_entry(index, entry) {
+                    Ok(key_val) => key_val,
+                    Err(e) => {
+                        self.recover_from(e)?;
+                        continue;
+                    }
+                }
+            };
+
+            if !emit {
+                continue;
+            }
+            if !self.allow_duplicate_keys && result.data.contains_key(&key) {
+                let e = self.error(ErrorKind::Parse, entry.key.span, "Duplicate Key.".to_string());
+                self.recover_from(e)?;
+                continue;
+            }
+            result.data.insert(key, value);
+        }
+
+        Ok(EconValue::Obj(result))
+    }
+
+    fn block_entry(&mut self, index: usize, entry: &'a Entry) -> Result<(String, EconValue), EconError> {
+        let depth = self.scopes.len() - 1;
+        self.scopes[depth].position = index;
+
+        let key = match self.value(&entry.key)? {
+            EconValue::Str(s) => s,
+            v => return self.fail(ErrorKind::Type, entry.key.span, format!("Expected Key got: {}.", v))
+        };
+
+        let literal = matches!(entry.key.kind, ExprKind::Str(_));
+        if literal {
+            self.scopes[depth].pending.remove(key.as_str());
+            self.path.push((depth, key.clone()));
+        }
+
+        let value = self.value(&entry.value);
+
+        if literal {
+            self.path.pop();
+        }
+
+        let value = value?;
+        self.scopes[depth].values.insert(key.clone(), value.clone());
+        Ok((key, value))
+    }
+
+    /// Evaluate `expr` with `values` bound in a frame over the innermost object.
+    fn bound(&mut self, values: HashMap<&'a str, EconValue>, expr: &'a Expr) -> Result<EconValue, EconError> {
+        let depth = self.scopes.len() - 1;
+        self.frames.push(Frame { depth, values });
+        let result = self.value(expr);
+        self.frames.pop();
+        result
+    }
+
+    fn check_val_with_constraint(&mut self, input: EconValue, span: Span) -> Result<EconValue, EconError> {
+        let ty = match input {
+            EconValue::Str(_) => "string",
+            EconValue::Bool(_) => "bool",
+            EconValue::Num(_) | EconValue::Int(_) => "number",
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(_) => "number",
+            EconValue::Nil => "nil",
+            _ => { return Ok(input); }
+        };
+
+        if self.in_constraint {
+            return Ok(input);
+        }
+
+        let mut to_use: Option<EconValue> = None;
+
+        for depth in (0..self.scopes.len()).rev() {
+            let scope = &self.scopes[depth];
+            let constraints: Vec<&'a Constraint> = match scope.constraints.get(ty) {
+                Some(c) => c.iter().filter(|(index, _)| *index < scope.position).map(|(_, c)| *c).collect(),
+                None => continue
+            };
+
+            self.in_constraint = true;
+            for constraint in constraints {
+                let current = to_use.clone().unwrap_or_else(|| input.clone());
+                match self.apply_constraint(constraint, current, span) {
+                    Ok(Some(v)) => to_use = Some(v),
+                    Ok(None) => {}
+                    Err(e) => {
+                        self.in_constraint = false;
+                        return Err(e);
+                    }
+                }
+            }
+            self.in_constraint = false;
+        }
+
+        Ok(to_use.unwrap_or(input))
+    }
+
+    /// Runs a single constraint/error macro against `current`. Returns the replacement value if the
+    /// constraint applied and an error if an error macro fired.
+    fn apply_constraint(&mut self, c: &'a Constraint, current: EconValue, span: Span) -> Result<Option<EconValue>, EconError> {
+        let name = if c.is_error {
+            format!("{} error", c.ty)
+        } else {
+            format!("{} constraint", c.ty)
+        };
+
+        let depth = self.scopes.len() - 1;
+        self.frames.push(Frame { depth, values: HashMap::from([(c.param.as_str(), current)]) });
+        let result = self.constraint_outcome(c, &name, span);
+        self.frames.pop();
+        result
+    }
+
+    fn constraint_outcome(&mut self, c: &'a Constraint, name: &str, span: Span) -> Result<Option<EconValue>, EconError> {
+        let applies = match (self.value(&c.condition)?, c.ty == "nil") {
+            (EconValue::Nil, true) => true,
+            (EconValue::Bool(b), false) => b,
+            _ => return self.fail(ErrorKind::Type, c.condition.span, format!("{}: condition must be boolean.", name))
+        };
+
+        if !applies {
+            return Ok(None);
+        }
+
+        match self.value(&c.value)? {
+            EconValue::Str(s) if c.is_error => self.fail(ErrorKind::Constraint, span, s),
+            v if c.is_error => self.fail(ErrorKind::Constraint, span, format!("{}", v)),
+            v => Ok(Some(v))
+        }
+    }
+
+    fn unary(&self, op: UnaryOp, right: EconValue, span: Span) -> Result<EconValue, EconError> {
+        match (op, right) {
+            (UnaryOp::Neg, EconValue::Num(n)) => Ok(EconValue::Num(-n)),
+            (UnaryOp::Neg, EconValue::Int(n)) => match n.checked_neg() {
+                Some(n) => Ok(EconValue::Int(n)),
+                None => self.fail(ErrorKind::Type, span, format!("Integer overflow in '-' of {}", n)),
+            },
+            #[cfg(feature = "decimal")]
+            (UnaryOp::Neg, EconValue::Dec(n)) => Ok(EconValue::Dec(-n)),
+            (UnaryOp::Not, EconValue::Bool(b)) => Ok(EconValue::Bool(!b)),
+            (UnaryOp::Neg | UnaryOp::Not, v) => Ok(v),
+            (UnaryOp::Len, EconValue::Str(s)) => Ok(EconValue::from(s.chars().count())),
+            (UnaryOp::Len, v @ (EconValue::Num(_) | EconValue::Int(_))) => Ok(v),
+            #[cfg(feature = "decimal")]
+            (UnaryOp::Len, v @ EconValue::Dec(_)) => Ok(v),
+            (UnaryOp::Len, EconValue::Arr(a)) => Ok(EconValue::from(a.len())),
+            (UnaryOp::Len, EconValue::Obj(o)) => Ok(EconValue::from(o.data.keys().len())),
+            (UnaryOp::Len, v) => self.fail(ErrorKind::Type, span, format!("Invalid '#' of type: {}", v))
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, left: EconValue, right: EconValue, span: Span) -> Result<EconValue, EconError> {
+        // An integer meeting a float is promoted to a float
+        let (left, right) = match (op, &left, &right) {
+            (BinaryOp::And | BinaryOp::Or, _, _) => (left, right),
+            (_, EconValue::Int(n), EconValue::Num(_)) => (EconValue::Num(*n as f64), right),
+            (_, EconValue::Num(_), EconValue::Int(n)) => (left, EconValue::Num(*n as f64)),
+            // With the `decimal` feature an integer meeting a decimal becomes a decimal, and a decimal
+            // meeting a float becomes a float
+            #[cfg(feature = "decimal")]
+            (_, EconValue::Int(n), EconValue::Dec(_)) => (EconValue::Dec((*n).into()), right),
+            #[cfg(feature = "decimal")]
+            (_, EconValue::Dec(_), EconValue::Int(n)) => (left, EconValue::Dec((*n).into())),
+            #[cfg(feature = "decimal")]
+            (_, EconValue::Dec(d), EconValue::Num(_)) => (EconValue::Num(EconValue::Dec(*d).as_f64().unwrap_or(f64::NAN)), right),
+            #[cfg(feature = "decimal")]
+            (_, EconValue::Num(_), EconValue::Dec(d)) => (left, EconValue::Num(EconValue::Dec(*d).as_f64().unwrap_or(f64::NAN))),
+            _ => (left, right),
+        };
+
+        let result = match (op, &left, &right) {
+            (BinaryOp::Equal, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 == n2),
+            (BinaryOp::NotEqual, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 != n2),
+            (BinaryOp::Less, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 < n2),
+            (BinaryOp::Greater, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 > n2),
+            (BinaryOp::GreaterEqual, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 >= n2),
+            (BinaryOp::LessEqual, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Bool(n1 <= n2),
+
+            (BinaryOp::Add, EconValue::Int(n1), EconValue::Int(n2)) => self.checked(op, n1.checked_add(*n2), &left, &right, span)?,
+            (BinaryOp::Sub, EconValue::Int(n1), EconValue::Int(n2)) => self.checked(op, n1.checked_sub(*n2), &left, &right, span)?,
+            (BinaryOp::Mul, EconValue::Int(n1), EconValue::Int(n2)) => self.checked(op, n1.checked_mul(*n2), &left, &right, span)?,
+            // Division stays an integer only when it is exact, 5 / 2 is 2.5 and 1 / 0 is inf
+            (BinaryOp::Div, EconValue::Int(n1), EconValue::Int(n2)) if *n2 != 0 && n1.checked_rem(*n2) == Some(0) => EconValue::Int(n1 / n2),
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Div, EconValue::Int(n1), EconValue::Int(n2)) if *n2 != 0 => self.decimal(op, (*n1).into(), (*n2).into(), span)?,
+            (BinaryOp::Div, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Num(*n1 as f64 / *n2 as f64),
+            (BinaryOp::Rem, EconValue::Int(n1), EconValue::Int(n2)) if *n2 != 0 => self.checked(op, n1.checked_rem_euclid(*n2), &left, &right, span)?,
+            (BinaryOp::Rem, EconValue::Int(n1), EconValue::Int(n2)) => EconValue::Num((*n1 as f64).rem_euclid(*n2 as f64)),
+
+            #[cfg(feature = "decimal")]
+            (
+                BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Less | BinaryOp::Greater | BinaryOp::GreaterEqual
+                | BinaryOp::LessEqual | BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem,
+                EconValue::Dec(d1),
+                EconValue::Dec(d2),
+            ) => self.decimal(op, *d1, *d2, span)?,
+
+            (BinaryOp::Equal, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 == n2),
+            (BinaryOp::Equal, EconValue::Bool(n1), EconValue::Bool(n2)) => EconValue::Bool(n1 == n2),
+            (BinaryOp::Equal, EconValue::Str(n1), EconValue::Str(n2)) => EconValue::Bool(n1 == n2),
+
+            (BinaryOp::NotEqual, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 != n2),
+            (BinaryOp::NotEqual, EconValue::Bool(n1), EconValue::Bool(n2)) => EconValue::Bool(n1 != n2),
+            (BinaryOp::NotEqual, EconValue::Str(n1), EconValue::Str(n2)) => EconValue::Bool(n1 != n2),
+
+            (BinaryOp::Less, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 < n2),
+            (BinaryOp::Greater, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 > n2),
+            (BinaryOp::GreaterEqual, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 >= n2),
+            (BinaryOp::LessEqual, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Bool(n1 <= n2),
+            (BinaryOp::Less | BinaryOp::Greater | BinaryOp::GreaterEqual | BinaryOp::LessEqual, EconValue::Str(n1), EconValue::Str(n2)) => {
+                let ordering = n1.to_lowercase().cmp(&n2.to_lowercase());
+                EconValue::Bool(match op {
+                    BinaryOp::Less => ordering.is_lt(),
+                    BinaryOp::Greater => ordering.is_gt(),
+                    BinaryOp::GreaterEqual => ordering.is_ge(),
+                    _ => ordering.is_le(),
+                })
+            }
+
+            (BinaryOp::And, EconValue::Bool(n1), EconValue::Bool(n2)) => EconValue::Bool(*n1 && *n2),
+            (BinaryOp::Or, EconValue::Bool(n1), EconValue::Bool(n2)) => EconValue::Bool(*n1 || *n2),
+            (BinaryOp::And | BinaryOp::Or, _, _) => left,
+
+            (BinaryOp::Add, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Num(n1 + n2),
+            (BinaryOp::Add, EconValue::Str(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Str(n1), EconValue::Num(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Num(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Str(n1), EconValue::Int(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Int(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Add, EconValue::Str(n1), EconValue::Dec(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Add, EconValue::Dec(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Str(n1), EconValue::Bool(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Bool(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}{}", n1, n2)),
+            (BinaryOp::Add, EconValue::Arr(n1), EconValue::Arr(n2)) => {
+                EconValue::Arr(n1.iter().chain(n2.iter()).cloned().collect())
+            }
+            (BinaryOp::Add, EconValue::Obj(n1), EconValue::Obj(n2)) => {
+                let mut new_obj = EconObj::new();
+
+                for (k, v) in n1.data.iter().chain(n2.data.iter()) {
+                    new_obj.data.insert(k.clone(), v.clone());
+                }
+
+                EconValue::Obj(new_obj)
+            }
+            (BinaryOp::Add, EconValue::Num(_) | EconValue::Int(_) | EconValue::Str(_) | EconValue::Arr(_) | EconValue::Obj(_), EconValue::Nil) => left,
+            (BinaryOp::Add, EconValue::Nil, EconValue::Num(_) | EconValue::Int(_) | EconValue::Str(_) | EconValue::Arr(_) | EconValue::Obj(_)) => right,
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Add, EconValue::Dec(_), EconValue::Nil) => left,
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Add, EconValue::Nil, EconValue::Dec(_)) => right,
+
+            (BinaryOp::Concat, EconValue::Str(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Str(n1), EconValue::Num(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Num(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Str(n1), EconValue::Int(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Int(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Concat, EconValue::Str(n1), EconValue::Dec(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            #[cfg(feature = "decimal")]
+            (BinaryOp::Concat, EconValue::Dec(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Str(n1), EconValue::Bool(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Bool(n1), EconValue::Str(n2)) => EconValue::Str(format!("{}\n{}", n1, n2)),
+            (BinaryOp::Concat, EconValue::Str(_), EconValue::Nil) => left,
+            (BinaryOp::Concat, EconValue::Nil, EconValue::Str(_)) => right,
+
+            (BinaryOp::Sub, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Num(n1 - n2),
+            (BinaryOp::Mul, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Num(n1 * n2),
+            (BinaryOp::Div, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Num(n1 / n2),
+            (BinaryOp::Rem, EconValue::Num(n1), EconValue::Num(n2)) => EconValue::Num(n1.rem_euclid(*n2)),
+
+            _ => {
+                return self.fail(ErrorKind::Type, span, format!("Invalid '{}' of types: {} and {}", op.symbol(), left, right));
+            }
+        };
+
+        Ok(result)
+    }
+
+    fn checked(&self, op: BinaryOp, result: Option<i64>, left: &EconValue, right: &EconValue, span: Span) -> Result<EconValue, EconError> {
+        match result {
+            Some(n) => Ok(EconValue::Int(n)),
+            None => self.fail(ErrorKind::Type, span, format!("Integer overflow in '{}' of {} and {}", op.symbol(), left, right)),
+        }
+    }
+
+    /// Exact arithmetic and comparison for the `decimal` feature. Dividing by zero falls back to floats
+    /// so `1.5 / 0` is still `inf`.
+    #[cfg(feature = "decimal")]
+    fn decimal(&self, op: BinaryOp, d1: rust_decimal::Decimal, d2: rust_decimal::Decimal, span: Span) -> Result<EconValue, EconError> {
+        let result = match op {
+            BinaryOp::Equal => return Ok(EconValue::Bool(d1 == d2)),
+            BinaryOp::NotEqual => return Ok(EconValue::Bool(d1 != d2)),
+            BinaryOp::Less => return Ok(EconValue::Bool(d1 < d2)),
+            BinaryOp::Greater => return Ok(EconValue::Bool(d1 > d2)),
+            BinaryOp::GreaterEqual => return Ok(EconValue::Bool(d1 >= d2)),
+            BinaryOp::LessEqual => return Ok(EconValue::Bool(d1 <= d2)),
+            BinaryOp::Div | BinaryOp::Rem if d2.is_zero() => {
+                let f1 = EconValue::Dec(d1).as_f64().unwrap_or(f64::NAN);
+                return self.binary(op, EconValue::Num(f1), EconValue::Num(0.0), span);
+            }
+            BinaryOp::Add => d1.checked_add(d2),
+            BinaryOp::Sub => d1.checked_sub(d2),
+            BinaryOp::Mul => d1.checked_mul(d2),
+            BinaryOp::Div => d1.checked_div(d2),
+            BinaryOp::Rem => d1.checked_rem(d2).map(|r| if r.is_sign_negative() && !r.is_zero() { r + d2.abs() } else { r }),
+            _ => {
+                return self.fail(ErrorKind::Type, span, format!("Invalid '{}' of types: {} and {}", op.symbol(), d1, d2));
+            }
+        };
+
+        match result {
+            Some(d) => Ok(EconValue::Dec(d)),
+            None => self.fail(ErrorKind::Type, span, format!("Decimal overflow in '{}' of {} and {}", op.symbol(), d1, d2)),
+        }
+    }
+
+    fn arity(&self, name: &str, args: &[Expr], count: usize, span: Span) -> Result<(), EconError> {
+        if args.len() != count {
+            return self.fail(ErrorKind::Type, span, format!("{}: Expected {} arguments got {}.", name, count, args.len()));
+        }
+        Ok(())
+    }
+
+    /// A lambda written in place or a reference to a function bound with `let` or `fn`.
+    fn lambda(&self, name: &str, arg: &'a Expr, params: usize) -> Result<Func<'a>, EconError> {
+        let func = match &arg.kind {
+            ExprKind::Lambda(lambda) => Some(Func { lambda, depth: None }),
+            ExprKind::Var(v) => self.function(v),
+            _ => None,
+        };
+
+        match func {
+            Some(func) if func.lambda.params.len() == params => Ok(func),
+            _ => self.fail(ErrorKind::Type, arg.span, format!("{}: Expected a function of {} reference(s).", name, params))
+        }
+    }
+
+    /// `$name(args)`, calling a function bound with `let` or `fn`.
+    fn apply(&mut self, v: &Var, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        let name = v.to_string();
+        let Some(func) = self.function(v) else {
+            return self.fail(ErrorKind::Reference, span, format!("{}: Not a function.", name));
+        };
+        self.arity(&name, args, func.lambda.params.len(), span)?;
+
+        let mut values = vec!();
+        for arg in args {
+            values.push(self.value(arg)?);
+        }
+        self.invoke(func, values, span)
+    }
+
+    /// Evaluate the body of `func` with its references bound to `args`. A bound function is evaluated in the
+    /// object it was bound in with the objects nested deeper and the calls made since set aside, like `force` does.
+    fn invoke(&mut self, func: Func<'a>, args: Vec<EconValue>, span: Span) -> Result<EconValue, EconError> {
+        let params = func.lambda.params.iter().map(|param| param.as_str()).zip(args).collect();
+        let Some(depth) = func.depth else {
+            return self.bound(params, &func.lambda.body);
+        };
+        if self.calls >= self.options.max_call_depth {
+            return self.fail(ErrorKind::Type, span, format!(
+                "Calls nest deeper than the maximum of {}.", self.options.max_call_depth
+            ));
+        }
+
+        self.calls += 1;
+        let result = self.within(depth, |this| this.bound(params, &func.lambda.body));
+        self.calls -= 1;
+        result
+    }
+
+    /// Run `f` in the object at `depth` as it was when entered. Objects nested deeper, the keys being evaluated
+    /// in them and calls made since are set aside meanwhile.
+    fn within<T>(&mut self, depth: usize, f: impl FnOnce(&mut Self) -> T) -> T {
+        let upper = self.scopes.split_off(depth + 1);
+        let calls = self.frames.split_off(self.scopes[depth].frames);
+        let keys = self.path.split_off(self.path.partition_point(|(key_depth, _)| *key_depth <= depth));
+
+        let result = f(self);
+
+        self.scopes.extend(upper);
+        self.frames.extend(calls);
+        self.path.extend(keys);
+        result
+    }
+
+    fn key_val(key: &str, val: &EconValue) -> EconValue {
+        let mut key_val = EconObj::new();
+        key_val.data.insert("key".to_string(), EconValue::Str(key.to_string()));
+        key_val.data.insert("val".to_string(), val.clone());
+        EconValue::Obj(key_val)
+    }
+
+    fn call(&mut self, func: &Function, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        let name = func.name();
+
+        match func {
+            Function::Filter => self.filter_impl(name, args, span),
+            Function::Map => self.map_impl(name, args, span),
+            Function::Fold => self.fold_impl(name, args, span),
+            Function::Sort => self.sort_impl(name, args, span),
+            Function::Zip => self.zip_impl(name, args, span),
+            Function::Native(_) => self.native_impl(name, args, span),
+            Function::Import => self.import_impl(name, args, span),
+            Function::Env | Function::EnvNum | Function::EnvBool => self.env_impl(func, name, args, span),
+            Function::Chars | Function::ToString | Function::Keys | Function::Values => {
+                self.arity(name, args, 1, span)?;
+                let right = self.value(&args[0])?;
+
+                match (func, right) {
+                    (Function::Chars, EconValue::Str(s)) => {
+                        Ok(EconValue::Arr(s.chars().map(|c| EconValue::Str(c.to_string())).collect()))
+                    }
+                    (Function::ToString, v) => Ok(EconValue::Str(Self::dig(&v))),
+                    (Function::Keys, EconValue::Obj(o)) => {
+                        Ok(EconValue::Arr(o.data.keys().map(|k| EconValue::Str(k.to_string())).collect()))
+                    }
+                    (Function::Values, EconValue::Obj(o)) => {
+                        Ok(EconValue::Arr(o.data.values().cloned().collect()))
+                    }
+                    (Function::Chars, v) => {
+                        self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected String got {}.", name, v))
+                    }
+                    (_, v) => {
+                        self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected Object got {}.", name, v))
+                    }
+                }
+            }
+        }
+    }
+
+    fn native_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        let Some((arity, f)) = self.options.native_fns.get(name).cloned() else {
+            return self.fail(ErrorKind::Reference, span, format!("{}: Function is not registered.", name));
+        };
+        self.arity(name, args, arity, span)?;
+
+        let mut values = vec!();
+        for arg in args {
+            values.push(self.value(arg)?);
+        }
+
+        f(&values).or_else(|e| {
+            let kind = match e.kind() {
+                kind @ (ErrorKind::Type | ErrorKind::Reference | ErrorKind::Constraint) => kind,
+                _ => ErrorKind::Type,
+            };
+            self.fail(kind, span, format!("{}: {}", name, e.message()))
+        })
+    }
+
+    /// Value of another file, relative to this one. Loaded through `options.resolver` and cached per id and context in `options.import_cache`.
+    fn import_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 1, span)?;
+        if !self.options.includes {
+            return self.fail(ErrorKind::Reference, span, format!("{}: Imports are disabled.", name));
+        }
+
+        let target = match self.value(&args[0])? {
+            EconValue::Str(s) => s,
+            v => return self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected String got {}.", name, v)),
+        };
+
+        let from = Path::new(self.file.as_deref().unwrap_or(""));
+        let (id, source) = match self.options.resolver.resolve(from, &target) {
+            Ok(file) => file,
+            Err(e) => return self.fail(ErrorKind::Reference, span, format!("{}: Cannot read '{}': {}.", name, target, e.message())),
+        };
+        if let Some(cycle) = import::cycle(&self.options.importing, &id) {
+            return self.fail(ErrorKind::Reference, span, format!("{}: Import cycle: {}.", name, cycle));
+        }
+        let context = import::Context::of(&self.options);
+        if let Some(value) = self.options.import_cache.get(&id, &context) {
+            return Ok(value);
+        }
+
+        let mut options = self.options.clone().source_name(id.as_str());
+        options.importing.push(id.clone());
+
+        let value = Econ::from_str(&source, options)?;
+        self.options.import_cache.insert(id, context, value.clone());
+        Ok(value)
+    }
+
+    /// `env(name)` or `env(name, default)`, the variable converted to a number or bool for `env_num` and `env_bool`.
+    /// The default is returned as is.
+    fn env_impl(&mut self, func: &Function, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        if args.is_empty() || args.len() > 2 {
+            return self.fail(ErrorKind::Type, span, format!("{}: Expected 1 or 2 arguments got {}.", name, args.len()));
+        }
+
+        let var = match self.value(&args[0])? {
+            EconValue::Str(s) => s,
+            v => return self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected String got {}.", name, v)),
+        };
+        if !self.options.env.allows(&var) {
+            return self.fail(ErrorKind::Reference, span, format!("{}: Environment variable '{}' is not accessible.", name, var));
+        }
+
+        let raw = match std::env::var(&var) {
+            Ok(raw) => raw,
+            Err(std::env::VarError::NotPresent) => {
+                return match args.get(1) {
+                    Some(default) => self.value(default),
+                    None => self.fail(ErrorKind::Reference, span, format!("{}: Environment variable '{}' is not set.", name, var)),
+                };
+            }
+            Err(std::env::VarError::NotUnicode(_)) => {
+                return self.fail(ErrorKind::Type, span, format!("{}: Environment variable '{}' is not valid unicode.", name, var));
+            }
+        };
+
+        let trimmed = raw.trim();
+        match func {
+            Function::EnvNum => {
+                if let Ok(n) = trimmed.parse::<i64>() {
+                    return Ok(EconValue::Int(n));
+                }
+                #[cfg(feature = "decimal")]
+                if let Ok(n) = rust_decimal::Decimal::from_str_exact(trimmed) {
+                    return Ok(EconValue::Dec(n));
+                }
+                match trimmed.parse::<f64>() {
+                    Ok(n) if n.is_finite() => Ok(EconValue::Num(n)),
+                    _ => self.fail(ErrorKind::Type, span, format!("{}: Environment variable '{}' is not a number got '{}'.", name, var, raw)),
+                }
+            }
+            Function::EnvBool => match trimmed.to_ascii_lowercase().as_str() {
+                "true" | "1" | "yes" | "on" => Ok(EconValue::Bool(true)),
+                "false" | "0" | "no" | "off" => Ok(EconValue::Bool(false)),
+                _ => self.fail(ErrorKind::Type, span, format!("{}: Environment variable '{}' is not a bool got '{}'.", name, var, raw)),
+            },
+            _ => Ok(EconValue::Str(raw)),
+        }
+    }
+
+    fn filter_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 2, span)?;
+        let right = self.value(&args[0])?;
+        let lambda = self.lambda(name, &args[1], 1)?;
+
+        match right {
+            EconValue::Arr(a) => {
+                let mut new_vec = vec!();
+                for item in a {
+                    if self.condition(name, lambda, vec!(item.clone()), span)? {
+                        new_vec.push(item);
+                    }
+                }
+                Ok(EconValue::Arr(new_vec))
+            }
+            EconValue::Obj(o) => {
+                let mut new_obj = EconObj::new();
+                for (k, v) in o.data {
+                    if self.condition(name, lambda, vec!(Self::key_val(&k, &v)), span)? {
+                        new_obj.data.insert(k, v);
+                    }
+                }
+                Ok(EconValue::Obj(new_obj))
+            }
+            v => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected Object/Array got {}.", name, v))
+            }
+        }
+    }
+
+    fn condition(&mut self, name: &str, lambda: Func<'a>, args: Vec<EconValue>, span: Span) -> Result<bool, EconError> {
+        match self.invoke(lambda, args, span)? {
+            EconValue::Bool(b) => Ok(b),
+            v => self.fail(ErrorKind::Type, span, format!("{}: condition must be boolean got {}.", name, v))
+        }
+    }
+
+    fn map_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 2, span)?;
+        let right = self.value(&args[0])?;
+        let lambda = self.lambda(name, &args[1], 1)?;
+
+        match right {
+            EconValue::Arr(a) => {
+                let mut new_vec = vec!();
+                for item in a {
+                    new_vec.push(self.invoke(lambda, vec!(item), span)?);
+                }
+                Ok(EconValue::Arr(new_vec))
+            }
+            EconValue::Obj(o) => {
+                let mut new_obj = EconObj::new();
+                for (k, v) in o.data {
+                    let mapped = self.invoke(lambda, vec!(Self::key_val(&k, &v)), span)?;
+                    new_obj.data.insert(k, mapped);
+                }
+                Ok(EconValue::Obj(new_obj))
+            }
+            v => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid argument expected Object/Array got {}.", name, v))
+            }
+        }
+    }
+
+    fn fold_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 2, span)?;
+        let right = self.value(&args[0])?;
+        let lambda = self.lambda(name, &args[1], 2)?;
+
+        let items: Vec<EconValue> = match right {
+            EconValue::Arr(a) => a,
+            EconValue::Obj(o) => o.data.iter().map(|(k, v)| Self::key_val(k, v)).collect(),
+            v => {
+                return self.fail(ErrorKind::Type, span, format!("{}: Invalid argument 1 expected an Array/Object got {}.", name, v));
+            }
+        };
+
+        let mut acc = EconValue::Nil;
+        for item in items {
+            acc = self.invoke(lambda, vec!(item, acc), span)?;
+        }
+        Ok(acc)
+    }
+
+    fn partition(&mut self, name: &str, lambda: Func<'a>, a: &mut [EconValue], span: Span) -> Result<usize, EconError> {
+        let mut i = 0;
+        let right = a.len() - 1;
+
+        for j in 0..right {
+            if self.condition(name, lambda, vec!(a[j].clone(), a[right].clone()), span)? {
+                a.swap(j, i);
+                i += 1;
+            }
+        }
+
+        a.swap(i, right);
+        Ok(i)
+    }
+
+    fn quicksort(&mut self, name: &str, lambda: Func<'a>, a: &mut [EconValue], span: Span) -> Result<(), EconError> {
+        if a.len() > 1 {
+            let q = self.partition(name, lambda, a, span)?;
+            self.quicksort(name, lambda, &mut a[..q], span)?;
+            self.quicksort(name, lambda, &mut a[q+1..], span)?;
+        }
+
+        Ok(())
+    }
+
+    fn sort_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 2, span)?;
+        let right = self.value(&args[0])?;
+        let lambda = self.lambda(name, &args[1], 2)?;
+
+        match right {
+            EconValue::Arr(mut a) => {
+                self.quicksort(name, lambda, &mut a[..], span)?;
+                Ok(EconValue::Arr(a))
+            }
+            v => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid argument 1 expected an Array/Object got {}.", name, v))
+            }
+        }
+    }
+
+    fn zip_impl(&mut self, name: &str, args: &'a [Expr], span: Span) -> Result<EconValue, EconError> {
+        self.arity(name, args, 2, span)?;
+        let a = self.value(&args[0])?;
+        let b = self.value(&args[1])?;
+
+        match (a, b) {
+            (EconValue::Arr(aa), EconValue::Arr(bb)) => {
+                let mut ret = vec!();
+                for i in 0..aa.len().max(bb.len()) {
+                    let av = aa.get(i).cloned().unwrap_or(EconValue::Nil);
+                    let bv = bb.get(i).cloned().unwrap_or(EconValue::Nil);
+                    ret.push(EconValue::Arr(vec![av, bv]));
+                }
+                Ok(EconValue::Arr(ret))
+            }
+            (EconValue::Arr(_), bb) => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid argument 2 expected an Array got {}.", name, bb))
+            }
+            (aa, EconValue::Arr(_)) => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid argument 1 expected an Array got {}.", name, aa))
+            }
+            (aa, bb) => {
+                self.fail(ErrorKind::Type, span, format!("{}: Invalid arguments expected Arrays got {} and {}.", name, aa, bb))
+            }
+        }
+    }
+
+    fn dig(current: &EconValue) -> String {
+        match current {
+            EconValue::Arr(a) => a.iter().map(Self::dig).collect(),
+            EconValue::Obj(o) => o.data.values().map(Self::dig).collect(),
+            EconValue::Bool(b) => b.to_string(),
+            EconValue::Num(n) => format!("{}", n),
+            EconValue::Int(n) => n.to_string(),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => n.to_string(),
+            EconValue::Str(s) => s.to_string(),
+            EconValue::Nil => String::from("nil"),
+        }
+    }
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row[b.len()]
+}
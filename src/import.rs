@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    path::{Component, Path},
+    sync::{Arc, Mutex},
+};
+
+use crate::{error::EconError, options::{EconOptions, EnvAccess}, value::EconValue};
+
+/// Values of files evaluated by `import(...)`, by [`CanonicalId`] and the options they were evaluated with.
+/// Shared by every clone of the [`EconOptions`] it belongs to, so a file imported by many configs parsed with
+/// the same options is only evaluated once, while options with other variables, environment access or
+/// functions evaluate it again.
+#[derive(Clone, Default)]
+pub struct ImportCache(Arc<Mutex<HashMap<CanonicalId, HashMap<Context, EconValue>>>>);
+
+/// Everything in [`EconOptions`] that can change what a file evaluates to besides its source. Functions and
+/// the resolver are told apart by address.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Context {
+    vars: Vec<(String, EconValue)>,
+    env: EnvAccess,
+    native_fns: Vec<(String, usize, usize)>,
+    resolver: usize,
+    flags: [bool; 5],
+    limits: [Option<usize>; 3],
+}
+
+impl Context {
+    pub(crate) fn of(options: &EconOptions) -> Self {
+        let mut vars: Vec<(String, EconValue)> = options.vars.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut native_fns: Vec<(String, usize, usize)> = options.native_fns.iter()
+            .map(|(name, (arity, f))| (name.clone(), *arity, Arc::as_ptr(f) as *const () as usize))
+            .collect();
+        native_fns.sort();
+
+        Self {
+            vars,
+            env: options.env.clone(),
+            native_fns,
+            resolver: Arc::as_ptr(&options.resolver) as *const () as usize,
+            flags: [options.strict_refs, options.allow_duplicate_keys, options.macros, options.functions, options.includes],
+            limits: [options.max_depth, options.max_source_len, Some(options.max_call_depth)],
+        }
+    }
+}
+
+impl fmt::Debug for ImportCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ImportCache").field(&self.len()).finish()
+    }
+}
+
+impl ImportCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values held, one per file and context it was imported with.
+    pub fn len(&self) -> usize {
+        self.0.lock().map(|c| c.values().map(HashMap::len).sum()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget every value so changed files are read again.
+    pub fn clear(&self) {
+        if let Ok(mut cache) = self.0.lock() {
+            cache.clear();
+        }
+    }
+
+    pub(crate) fn get(&self, id: &str, context: &Context) -> Option<EconValue> {
+        self.0.lock().ok()?.get(id)?.get(context).cloned()
+    }
+
+    pub(crate) fn insert(&self, id: String, context: Context, value: EconValue) {
+        if let Ok(mut cache) = self.0.lock() {
+            cache.entry(id).or_default().insert(context, value);
+        }
+    }
+}
+
+/// Identifies a loaded file so cycles are detected and imports cached whatever path was used to reach it.
+/// For [`FsResolver`] this is the canonical path, for the in-memory resolvers the normalized name.
+pub type CanonicalId = String;
+
+/// Where `import(...)`, `@include(...)`, [`Econ::from_path`](crate::econ::Econ::from_path) and
+/// [`Econ::create`](crate::econ::Econ::create) get their source from. `from` is the id of the file doing the
+/// loading, or `""` for the top level, and `spec` is the path as written.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, import::MemoryResolver, options::EconOptions};
+///
+/// let files = MemoryResolver::new()
+///     .file("main.econ", "{ db: import(\"shared/db.econ\") }")
+///     .file("shared/db.econ", "{ host: \"localhost\", ports: import(\"../ports.econ\") }")
+///     .file("ports.econ", "{ db: 5432 }");
+///
+/// let obj = Econ::from_path("main.econ", EconOptions::new().resolver(files)).unwrap();
+/// assert_eq!(Some(5432f64), obj["db"]["ports"]["db"].as_f64());
+/// ```
+pub trait SourceResolver: Send + Sync {
+    fn resolve(&self, from: &Path, spec: &str) -> Result<(CanonicalId, String), EconError>;
+}
+
+/// Reads files from disk relative to the file doing the loading, or the working directory. The default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsResolver;
+
+impl SourceResolver for FsResolver {
+    fn resolve(&self, from: &Path, spec: &str) -> Result<(CanonicalId, String), EconError> {
+        let path = from.parent().unwrap_or(Path::new("")).join(spec);
+        let source = fs::read_to_string(&path).map_err(|e| EconError::Io {
+            path: path.display().to_string(),
+            kind: e.kind(),
+            message: e.to_string(),
+        })?;
+        let id = fs::canonicalize(&path).unwrap_or(path);
+        Ok((id.display().to_string(), source))
+    }
+}
+
+/// Files held in memory by name. Names are `/` separated and relative paths resolve against the directory
+/// of the file doing the loading, `.` and `..` included.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn file(mut self, name: &str, source: impl Into<String>) -> Self {
+        self.insert(name, source);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, source: impl Into<String>) -> Option<String> {
+        self.files.insert(normalize(Path::new(name)), source.into())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.files.remove(&normalize(Path::new(name)))
+    }
+}
+
+impl SourceResolver for MemoryResolver {
+    fn resolve(&self, from: &Path, spec: &str) -> Result<(CanonicalId, String), EconError> {
+        let id = join(from, spec);
+        match self.files.get(&id) {
+            Some(source) => Ok((id, source.clone())),
+            None => Err(not_found(id)),
+        }
+    }
+}
+
+/// Files compiled into the binary, typically with `include_str!`. Names resolve like [`MemoryResolver`]'s.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, import::EmbeddedResolver, options::EconOptions};
+///
+/// static FILES: EmbeddedResolver = EmbeddedResolver::new(&[
+///     ("main.econ", "{ limits: import(\"limits.econ\") }"),
+///     ("limits.econ", "{ max: 10 }"), // e.g. include_str!("config/limits.econ")
+/// ]);
+///
+/// let obj = Econ::create("main.econ", EconOptions::new().resolver(FILES)).unwrap();
+/// assert_eq!(Some(10f64), obj["limits"]["max"].as_f64());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedResolver {
+    files: &'static [(&'static str, &'static str)],
+}
+
+impl EmbeddedResolver {
+    pub const fn new(files: &'static [(&'static str, &'static str)]) -> Self {
+        Self { files }
+    }
+}
+
+impl SourceResolver for EmbeddedResolver {
+    fn resolve(&self, from: &Path, spec: &str) -> Result<(CanonicalId, String), EconError> {
+        let id = join(from, spec);
+        match self.files.iter().find(|(name, _)| normalize(Path::new(name)) == id) {
+            Some((_, source)) => Ok((id, source.to_string())),
+            None => Err(not_found(id)),
+        }
+    }
+}
+
+/// `spec` relative to the directory of `from`, normalized.
+fn join(from: &Path, spec: &str) -> String {
+    normalize(&from.parent().unwrap_or(Path::new("")).join(spec))
+}
+
+/// `path` with `.` dropped and `..` applied, `/` separated.
+fn normalize(path: &Path) -> String {
+    let mut parts: Vec<String> = vec!();
+    let mut root = false;
+    for component in path.components() {
+        match component {
+            Component::RootDir | Component::Prefix(_) => root = true,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(parts.last(), Some(p) if p != "..") {
+                    parts.pop();
+                } else if !root {
+                    parts.push("..".to_string());
+                }
+            }
+            Component::Normal(p) => parts.push(p.to_string_lossy().into_owned()),
+        }
+    }
+
+    let path = parts.join("/");
+    if root {
+        format!("/{}", path)
+    } else {
+        path
+    }
+}
+
+fn not_found(id: String) -> EconError {
+    EconError::Io {
+        path: id,
+        kind: io::ErrorKind::NotFound,
+        message: "No such file.".to_string(),
+    }
+}
+
+/// `a -> b -> a` if loading `path` after `chain` would be a cycle.
+pub(crate) fn cycle(chain: &[String], path: &str) -> Option<String> {
+    let start = chain.iter().position(|p| p == path)?;
+    let mut names: Vec<&str> = chain[start..].iter().map(String::as_str).collect();
+    names.push(path);
+    Some(names.join(" -> "))
+}
@@ -0,0 +1,264 @@
+use std::{slice, vec};
+
+use indexmap::map;
+
+use crate::{object::EconObj, path, value::EconValue};
+
+/// Borrowing iterator over the elements of an array or the values of an object.
+pub struct Iter<'a>(IterKind<'a>);
+
+enum IterKind<'a> {
+    Arr(slice::Iter<'a, EconValue>),
+    Obj(map::Values<'a, String, EconValue>),
+    Empty,
+}
+
+/// Mutable iterator over the elements of an array or the values of an object.
+pub struct IterMut<'a>(IterMutKind<'a>);
+
+enum IterMutKind<'a> {
+    Arr(slice::IterMut<'a, EconValue>),
+    Obj(map::ValuesMut<'a, String, EconValue>),
+    Empty,
+}
+
+/// Owning iterator over the elements of an array or the values of an object.
+pub struct IntoIter(IntoIterKind);
+
+enum IntoIterKind {
+    Arr(vec::IntoIter<EconValue>),
+    Obj(map::IntoValues<String, EconValue>),
+    Empty,
+}
+
+/// Depth first walk over a value and everything nested in it, see [`EconValue::walk`].
+pub struct Walk<'a> {
+    stack: Vec<(String, &'a EconValue)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a EconValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterKind::Arr(i) => i.next(),
+            IterKind::Obj(i) => i.next(),
+            IterKind::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterKind::Arr(i) => i.size_hint(),
+            IterKind::Obj(i) => i.size_hint(),
+            IterKind::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = &'a mut EconValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IterMutKind::Arr(i) => i.next(),
+            IterMutKind::Obj(i) => i.next(),
+            IterMutKind::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IterMutKind::Arr(i) => i.size_hint(),
+            IterMutKind::Obj(i) => i.size_hint(),
+            IterMutKind::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl Iterator for IntoIter {
+    type Item = EconValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            IntoIterKind::Arr(i) => i.next(),
+            IntoIterKind::Obj(i) => i.next(),
+            IntoIterKind::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.0 {
+            IntoIterKind::Arr(i) => i.size_hint(),
+            IntoIterKind::Obj(i) => i.size_hint(),
+            IntoIterKind::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (String, &'a EconValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+
+        match value {
+            EconValue::Arr(a) => {
+                for (i, v) in a.iter().enumerate().rev() {
+                    self.stack.push((path::index(&path, i), v));
+                }
+            }
+            EconValue::Obj(o) => {
+                for (k, v) in o.data.iter().rev() {
+                    self.stack.push((path::key(&path, k), v));
+                }
+            }
+            _ => {}
+        }
+
+        Some((path, value))
+    }
+}
+
+impl EconValue {
+    /// The elements of an array or the values of an object, nothing for anything else.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(match self {
+            EconValue::Arr(a) => IterKind::Arr(a.iter()),
+            EconValue::Obj(o) => IterKind::Obj(o.data.values()),
+            _ => IterKind::Empty,
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(match self {
+            EconValue::Arr(a) => IterMutKind::Arr(a.iter_mut()),
+            EconValue::Obj(o) => IterMutKind::Obj(o.data.values_mut()),
+            _ => IterMutKind::Empty,
+        })
+    }
+
+    /// The entries of an object in source order, nothing for anything else.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &EconValue)> {
+        self.as_object().into_iter().flat_map(|o| o.data.iter())
+    }
+
+    /// The keys of an object in source order, nothing for anything else.
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.as_object().into_iter().flat_map(|o| o.data.keys())
+    }
+
+    /// This value and everything nested in it, depth first in source order, each with the
+    /// [`path`](EconValue::path) that leads to it. The value itself comes first with the path `""`.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let obj = Econ::from("{ db: { users: [\"a\", \"b\"] }, \"api.key\": \"secret\" }");
+    /// let leaves: Vec<String> = obj.walk()
+    ///     .filter(|(_, v)| v.as_str().is_some())
+    ///     .map(|(path, _)| path)
+    ///     .collect();
+    /// assert_eq!(vec!["db.users[0]", "db.users[1]", "[\"api.key\"]"], leaves);
+    /// assert_eq!(Some("secret"), obj.path(&leaves[2]).unwrap().as_str());
+    /// ```
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(String::new(), self)],
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a EconValue {
+    type Item = &'a EconValue;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut EconValue {
+    type Item = &'a mut EconValue;
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for EconValue {
+    type Item = EconValue;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(match self {
+            EconValue::Arr(a) => IntoIterKind::Arr(a.into_iter()),
+            EconValue::Obj(o) => IntoIterKind::Obj(o.data.into_values()),
+            _ => IntoIterKind::Empty,
+        })
+    }
+}
+
+impl EconObj {
+    pub fn iter(&self) -> map::Iter<'_, String, EconValue> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> map::IterMut<'_, String, EconValue> {
+        self.data.iter_mut()
+    }
+
+    pub fn keys(&self) -> map::Keys<'_, String, EconValue> {
+        self.data.keys()
+    }
+
+    pub fn values(&self) -> map::Values<'_, String, EconValue> {
+        self.data.values()
+    }
+
+    pub fn values_mut(&mut self) -> map::ValuesMut<'_, String, EconValue> {
+        self.data.values_mut()
+    }
+}
+
+impl<'a> IntoIterator for &'a EconObj {
+    type Item = (&'a String, &'a EconValue);
+    type IntoIter = map::Iter<'a, String, EconValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut EconObj {
+    type Item = (&'a String, &'a mut EconValue);
+    type IntoIter = map::IterMut<'a, String, EconValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.iter_mut()
+    }
+}
+
+impl IntoIterator for EconObj {
+    type Item = (String, EconValue);
+    type IntoIter = map::IntoIter<String, EconValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
+impl FromIterator<(String, EconValue)> for EconObj {
+    fn from_iter<I: IntoIterator<Item = (String, EconValue)>>(iter: I) -> Self {
+        Self {
+            data: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromIterator<EconValue> for EconValue {
+    fn from_iter<I: IntoIterator<Item = EconValue>>(iter: I) -> Self {
+        EconValue::Arr(iter.into_iter().collect())
+    }
+}
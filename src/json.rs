@@ -0,0 +1,317 @@
+use std::fmt::Write as _;
+
+use crate::{error::EconError, object::EconObj, value::EconValue};
+
+/// How numbers Json can't represent, `inf`, `-inf` and `NaN`, are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NonFinite {
+    /// Fail with an `EconError::Serialize` naming the key
+    Error,
+    /// Write `null`
+    Null,
+    /// Write `"inf"`, `"-inf"` or `"NaN"`
+    String,
+}
+
+/// Controls how [`EconValue::to_json`] lays out its output.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, json::{JsonOptions, NonFinite}};
+///
+/// let obj = Econ::from("{ b: \"line\\\"1\\\"\", a: [1, 1 / 0] }");
+/// let options = JsonOptions::new().compact().sort_keys(true).non_finite(NonFinite::Null);
+/// assert_eq!(r#"{"a":[1,null],"b":"line\"1\""}"#, obj.to_json(&options).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Written once per level of nesting. `None` puts everything on one line.
+    pub indent: Option<String>,
+    pub sort_keys: bool,
+    pub non_finite: NonFinite,
+    /// Sort keys by UTF-16 code units and write numbers the way ECMAScript does, as RFC 8785 requires.
+    pub canonical: bool,
+}
+
+impl Default for JsonOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonOptions {
+    /// Tab indented, keys in source order, non finite numbers are an error.
+    pub fn new() -> Self {
+        Self {
+            indent: Some("\t".to_string()),
+            sort_keys: false,
+            non_finite: NonFinite::Error,
+            canonical: false,
+        }
+    }
+
+    /// RFC 8785 canonical form: one line, keys sorted by UTF-16 code units, numbers in their shortest
+    /// ECMAScript form and non finite numbers an error.
+    pub fn canonical() -> Self {
+        Self {
+            indent: None,
+            sort_keys: true,
+            non_finite: NonFinite::Error,
+            canonical: true,
+        }
+    }
+
+    /// What `Display` uses: `new` but non finite numbers become `null`.
+    pub(crate) fn display() -> Self {
+        Self::new().non_finite(NonFinite::Null)
+    }
+
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = Some(indent.into());
+        self
+    }
+
+    /// Everything on one line with no whitespace between tokens.
+    pub fn compact(mut self) -> Self {
+        self.indent = None;
+        self
+    }
+
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+}
+
+impl EconValue {
+    /// Render as Json, RFC 8259, laid out according to `options`. Only fails on a non finite number when
+    /// `options.non_finite` is `NonFinite::Error`.
+    pub fn to_json(&self, options: &JsonOptions) -> Result<String, EconError> {
+        let mut writer = JsonWriter::new(options);
+        writer.value(self, 0)?;
+        Ok(writer.out)
+    }
+}
+
+impl EconValue {
+    /// Render in RFC 8785 canonical form. Values that are equal produce byte identical output no matter
+    /// the order keys were written in or how numbers were spelled, so the result can be hashed or diffed.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let a = Econ::from("{ b: [1.0, 20.00], a: \"x\" }");
+    /// let b = Econ::from("{ a: \"x\", b: [1, 10 * 2] }");
+    /// assert_eq!(r#"{"a":"x","b":[1,20]}"#, a.to_canonical_json().unwrap());
+    /// assert_eq!(a.to_canonical_json().unwrap(), b.to_canonical_json().unwrap());
+    /// ```
+    pub fn to_canonical_json(&self) -> Result<String, EconError> {
+        self.to_json(&JsonOptions::canonical())
+    }
+}
+
+impl EconObj {
+    /// Render as a Json object, see [`EconValue::to_json`].
+    pub fn to_json(&self, options: &JsonOptions) -> Result<String, EconError> {
+        let mut writer = JsonWriter::new(options);
+        writer.obj(self, 0)?;
+        Ok(writer.out)
+    }
+}
+
+pub(crate) struct JsonWriter<'a> {
+    options: &'a JsonOptions,
+    pub(crate) out: String,
+}
+
+impl<'a> JsonWriter<'a> {
+    pub(crate) fn new(options: &'a JsonOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+        }
+    }
+
+    pub(crate) fn value(&mut self, value: &EconValue, depth: usize) -> Result<(), EconError> {
+        match value {
+            EconValue::Nil => self.out.push_str("null"),
+            EconValue::Bool(b) => self.out.push_str(if *b { "true" } else { "false" }),
+            EconValue::Num(n) => self.num(*n)?,
+            EconValue::Int(n) => {
+                let _ = write!(self.out, "{}", n);
+            }
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) if self.options.canonical && n.is_zero() => self.out.push('0'),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) if self.options.canonical => {
+                let digits = n.mantissa().unsigned_abs().to_string();
+                let point = digits.len() as i32 - n.scale() as i32;
+                self.out.push_str(&es_format(n.is_sign_negative(), digits.trim_end_matches('0'), point));
+            }
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => {
+                let _ = write!(self.out, "{}", n);
+            }
+            EconValue::Str(s) => self.string(s),
+            EconValue::Arr(a) => self.arr(a, depth)?,
+            EconValue::Obj(o) => self.obj(o, depth)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn arr(&mut self, arr: &[EconValue], depth: usize) -> Result<(), EconError> {
+        if arr.is_empty() {
+            self.out.push_str("[]");
+            return Ok(());
+        }
+
+        self.out.push('[');
+        for (i, v) in arr.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            self.value(v, depth + 1).map_err(|e| e.at(&format!("[{}]", i)))?;
+        }
+        self.newline(depth);
+        self.out.push(']');
+        Ok(())
+    }
+
+    pub(crate) fn obj(&mut self, obj: &EconObj, depth: usize) -> Result<(), EconError> {
+        if obj.data.is_empty() {
+            self.out.push_str("{}");
+            return Ok(());
+        }
+
+        let mut entries: Vec<(&String, &EconValue)> = obj.data.iter().collect();
+        if self.options.canonical {
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+        } else if self.options.sort_keys {
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+        }
+
+        self.out.push('{');
+        for (i, (k, v)) in entries.into_iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(depth + 1);
+            self.string(k);
+            self.out.push(':');
+            if self.options.indent.is_some() {
+                self.out.push(' ');
+            }
+            self.value(v, depth + 1).map_err(|e| e.at(k))?;
+        }
+        self.newline(depth);
+        self.out.push('}');
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = &self.options.indent {
+            self.out.push('\n');
+            for _ in 0..depth {
+                self.out.push_str(indent);
+            }
+        }
+    }
+
+    fn num(&mut self, n: f64) -> Result<(), EconError> {
+        if n.is_finite() && self.options.canonical {
+            self.out.push_str(&es_number(n));
+            return Ok(());
+        }
+        if n.is_finite() {
+            let _ = write!(self.out, "{}", n);
+            return Ok(());
+        }
+
+        match self.options.non_finite {
+            NonFinite::Error => Err(EconError::Serialize {
+                path: String::new(),
+                message: format!("{} is not a valid Json number.", n),
+            }),
+            NonFinite::Null => {
+                self.out.push_str("null");
+                Ok(())
+            }
+            NonFinite::String => {
+                let _ = write!(self.out, "\"{}\"", n);
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\u{08}' => self.out.push_str("\\b"),
+                '\u{0c}' => self.out.push_str("\\f"),
+                c if c < ' ' => {
+                    let _ = write!(self.out, "\\u{:04x}", c as u32);
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
+
+/// `n` formatted like ECMAScript's `Number.prototype.toString`: the shortest digits that round trip, written
+/// out in full between 1e-7 and 1e21 and in exponent form outside of it.
+fn es_number(n: f64) -> String {
+    if n == 0.0 {
+        return "0".to_string();
+    }
+
+    // `{:e}` gives the shortest round tripping digits as d.ddde<exp>
+    let sci = format!("{:e}", n.abs());
+    let (mantissa, exp) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    es_format(n < 0.0, &digits, exp.parse::<i32>().unwrap_or(0) + 1)
+}
+
+/// The number `0.digits * 10^point` written the way ECMAScript does. `digits` has no leading or
+/// trailing zeros.
+fn es_format(negative: bool, digits: &str, point: i32) -> String {
+    let k = digits.len() as i32;
+
+    let mut result = String::new();
+    if negative {
+        result.push('-');
+    }
+
+    if k <= point && point <= 21 {
+        result.push_str(digits);
+        result.extend(std::iter::repeat_n('0', (point - k) as usize));
+    } else if 0 < point && point <= 21 {
+        result.push_str(&digits[..point as usize]);
+        result.push('.');
+        result.push_str(&digits[point as usize..]);
+    } else if -6 < point && point <= 0 {
+        result.push_str("0.");
+        result.extend(std::iter::repeat_n('0', (-point) as usize));
+        result.push_str(digits);
+    } else {
+        result.push_str(&digits[..1]);
+        if k > 1 {
+            result.push('.');
+            result.push_str(&digits[1..]);
+        }
+        let _ = write!(result, "e{}{}", if point > 0 { "+" } else { "-" }, (point - 1).abs());
+    }
+
+    result
+}
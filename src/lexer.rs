@@ -1,6 +1,8 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, path::Path, sync::Arc};
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::{error::{Diagnostic, EconError, Span}, import, options::EconOptions};
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Function {
     Filter,
@@ -12,6 +14,33 @@ pub enum Function {
     Fold,
     Sort,
     Zip,
+    Import,
+    Env,
+    EnvNum,
+    EnvBool,
+    /// Registered with [`EconOptions::register_fn`]
+    Native(String),
+}
+
+impl Function {
+    pub fn name(&self) -> &str {
+        match self {
+            Function::Filter => "filter",
+            Function::Map => "map",
+            Function::Chars => "chars",
+            Function::ToString => "to_string",
+            Function::Keys => "keys",
+            Function::Values => "values",
+            Function::Fold => "fold",
+            Function::Sort => "sort",
+            Function::Zip => "zip",
+            Function::Import => "import",
+            Function::Env => "env",
+            Function::EnvNum => "env_num",
+            Function::EnvBool => "env_bool",
+            Function::Native(name) => name,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,6 +52,9 @@ pub enum Token {
     RightBracket,
     Comma,
     Num(f64),
+    Int(i64),
+    #[cfg(feature = "decimal")]
+    Dec(rust_decimal::Decimal),
     Bool(bool),
     Str(String),
     Var((isize, String)),
@@ -49,6 +81,8 @@ pub enum Token {
     Sharp,
     Percent,
     Arrow,
+    /// `=` in `let name = value`
+    Assign,
     SemiColon,
     Question,
     Exclaim,
@@ -61,48 +95,105 @@ pub enum Token {
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenData {
     pub token: Token,
-    pub line: usize,
+    pub span: Span,
+}
+
+impl TokenData {
+    pub fn line(&self) -> usize {
+        self.span.line
+    }
 }
 
 impl fmt::Display for TokenData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Line:[{:04}] -> {:?}", self.line, self.token)?;
+        write!(
+            f,
+            "Line:[{:04}:{:03}] -> {:?}",
+            self.span.line, self.span.column, self.token
+        )?;
 
         Ok(())
     }
 }
 
 pub struct EconLexer<'a> {
-    pub source: String,
+    pub source: Arc<str>,
     line: usize,
+    line_start: usize,
     start: usize,
+    token_start: usize,
     current: usize,
     macros: HashMap<String, (Vec<TokenData>, Vec<TokenData>)>,
     source_as_vec: Vec<&'a str>,
+    offsets: Vec<usize>,
     current_string_read: String,
+    /// The last token was `let`, so the next word is a binding name and ends at `=`.
+    binding: bool,
+    options: EconOptions,
 }
 
 impl<'a> EconLexer<'a> {
     pub fn init(source: &'a str) -> Self {
+        Self::with_options(source, EconOptions::new())
+    }
+
+    pub fn with_options(source: &'a str, options: EconOptions) -> Self {
         Self {
-            source: String::from(source),
+            source: Arc::from(source),
             start: 0,
+            token_start: 0,
             current: 0,
             line: 0,
+            line_start: 0,
             macros: HashMap::new(),
             source_as_vec: source.graphemes(true).collect::<Vec<&'a str>>(),
+            offsets: source
+                .grapheme_indices(true)
+                .map(|(i, _)| i)
+                .chain(std::iter::once(source.len()))
+                .collect(),
             current_string_read: String::from(""),
+            binding: false,
+            options,
         }
     }
 
-    fn error<T>(&self, msg: String) -> Result<T, String> {
-        Err(format!(
-            "Line:[{:04}] -> Error Lexing -> {}",
-            self.line,
-            msg.clone()
+    fn error<T>(&self, msg: String) -> Result<T, EconError> {
+        Err(EconError::Lex(
+            Diagnostic::new(msg, self.span_from(self.token_start))
+                .with_source(self.source.clone())
+                .in_file(self.options.source_name.clone()),
         ))
     }
 
+    fn byte_offset(&self, index: usize) -> usize {
+        self.offsets[index.min(self.offsets.len() - 1)]
+    }
+
+    /// Span from the grapheme at `start` up to the current position. Tokens never straddle lines except
+    /// for multi-line strings, which report the line and column they begin on.
+    fn span_from(&self, start: usize) -> Span {
+        let (line, column) = self.position_of(start);
+        Span::new(line, column, self.byte_offset(start), self.byte_offset(self.current.max(start)))
+    }
+
+    fn position_of(&self, index: usize) -> (usize, usize) {
+        if index >= self.line_start {
+            return (self.line, index - self.line_start);
+        }
+
+        let newlines = self.source_as_vec[index..self.line_start]
+            .iter()
+            .filter(|g| Self::is_newline(g))
+            .count();
+        let line_start = self.source_as_vec[..index]
+            .iter()
+            .rposition(|g| Self::is_newline(g))
+            .map(|p| p + 1)
+            .unwrap_or(0);
+        (self.line - newlines, index - line_start)
+    }
+
     fn peek(&self) -> Option<&str> {
         self.source_as_vec.get(self.current).copied()
     }
@@ -116,10 +207,7 @@ impl<'a> EconLexer<'a> {
     }
 
     fn advance(&mut self) -> Option<&str> {
-        let t = self.source_as_vec.get(self.current).copied().unwrap();
-        self.current_string_read.push_str(t);
-
-        self.current += 1;
+        self.eat();
         self.source_as_vec.get(self.current - 1).copied()
     }
 
@@ -128,37 +216,34 @@ impl<'a> EconLexer<'a> {
         self.current_string_read.push_str(t);
 
         self.current += 1;
+        if Self::is_newline(t) {
+            self.line += 1;
+            self.line_start = self.current;
+        }
     }
 
-    fn make_token(&self, t: Token) -> Result<TokenData, String> {
+    fn make_token(&self, t: Token) -> Result<TokenData, EconError> {
         Ok(TokenData {
             token: t,
-            line: self.line,
+            span: self.span_from(self.token_start),
         })
     }
 
-    fn skip_whitespace(&mut self) -> Result<(), String> {
+    fn skip_whitespace(&mut self) -> Result<(), EconError> {
         loop {
             match self.peek() {
-                Some(" ") | Some("\t") => {
+                Some(" ") | Some("\t") | Some("\r") | Some("\n") | Some("\r\n") => {
                     self.eat();
                 }
                 Some("/") => {
                     if let Some("/") = self.peek_next() {
-                        loop {
+                        while !matches!(self.peek(), None | Some("\n") | Some("\r\n")) {
                             self.eat();
-                            if let Some("\n") = self.peek() {
-                                break;
-                            }
                         }
                     } else {
                         break;
                     }
                 }
-                Some("\n") => {
-                    self.line += 1;
-                    self.eat();
-                }
                 _ => {
                     return Ok(());
                 }
@@ -172,11 +257,15 @@ impl<'a> EconLexer<'a> {
         self.current >= self.source_as_vec.len()
     }
 
+    fn is_newline(c: &str) -> bool {
+        c == "\n" || c == "\r\n"
+    }
+
     fn is_digit(c: &str) -> bool {
-        c >= "0" && c <= "9"
+        ("0"..="9").contains(&c)
     }
 
-    fn number(&mut self) -> Result<TokenData, String> {
+    fn number(&mut self) -> Result<TokenData, EconError> {
         loop {
             if let Some(v) = self.peek() {
                 if Self::is_digit(v) {
@@ -189,9 +278,12 @@ impl<'a> EconLexer<'a> {
             }
         }
 
+        let mut fraction = false;
+
         if let (Some(v), Some(n)) = (self.peek(), self.peek_next()) {
             if v == "." && Self::is_digit(n) {
                 self.eat();
+                fraction = true;
 
                 loop {
                     if let Some(v) = self.peek() {
@@ -207,6 +299,19 @@ impl<'a> EconLexer<'a> {
             }
         }
 
+        // Without a fraction the literal is an integer, unless it is too big to be one
+        if !fraction {
+            if let Ok(val) = self.current_string_read.parse::<i64>() {
+                return self.make_token(Token::Int(val));
+            }
+        }
+
+        // Anything else is exact when the `decimal` feature is on, as long as it fits in 28 digits
+        #[cfg(feature = "decimal")]
+        if let Ok(val) = rust_decimal::Decimal::from_str_exact(&self.current_string_read) {
+            return self.make_token(Token::Dec(val));
+        }
+
         let string_to_use = self.current_string_read.parse::<f64>();
 
         match string_to_use {
@@ -215,7 +320,7 @@ impl<'a> EconLexer<'a> {
         }
     }
 
-    fn string(&mut self) -> Result<TokenData, String> {
+    fn string(&mut self) -> Result<TokenData, EconError> {
         while let Some(v) = self.peek() {
             if v == "\\" {
                 self.eat();
@@ -234,11 +339,11 @@ impl<'a> EconLexer<'a> {
         } else {
             let build = self.current_string_read[1..].to_string();
             self.eat();
-            self.make_token(Token::Str(String::from(build.replace("\\\"", "\""))))
+            self.make_token(Token::Str(build.replace("\\\"", "\"")))
         }
     }
 
-    fn variable(&mut self) -> Result<TokenData, String> {
+    fn variable(&mut self) -> Result<TokenData, EconError> {
         self.current_string_read = String::from("");
         while let Some(v) = self.peek() {
             if let "/" | "*" | "+" | "-" | "(" | ")" | " " | "\n" | "\r" | "." | "," | "[" | "]" | ";"
@@ -276,16 +381,13 @@ impl<'a> EconLexer<'a> {
                 self.current_string_read.to_string()
             };
 
-            Ok(TokenData {
-                token: Token::Var((search, build)),
-                line: self.line,
-            })
+            self.make_token(Token::Var((search, build)))
         }
     }
 
-    fn keyword(&mut self) -> Result<TokenData, String> {
+    fn keyword(&mut self) -> Result<TokenData, EconError> {
         while let Some(v) = self.peek() {
-            if !Self::is_alpha(v) {
+            if !Self::is_alpha(v) || (self.binding && v == "=") {
                 break;
             }
             self.eat();
@@ -297,9 +399,7 @@ impl<'a> EconLexer<'a> {
             self.make_token(Token::Bool(true))
         } else if build == "false" {
             self.make_token(Token::Bool(false))
-        } else if build == "nil" {
-            self.make_token(Token::Nil)
-        } else if build == "null" {
+        } else if build == "nil" || build == "null" {
             self.make_token(Token::Nil)
         } else if build == "not" {
             self.make_token(Token::Not)
@@ -327,28 +427,44 @@ impl<'a> EconLexer<'a> {
             self.make_token(Token::Fn(Function::Sort))
         } else if build == "zip" {
             self.make_token(Token::Fn(Function::Zip))
+        } else if build == "import" && self.peek() == Some("(") {
+            self.make_token(Token::Fn(Function::Import))
+        } else if build == "env" && self.peek() == Some("(") {
+            self.make_token(Token::Fn(Function::Env))
+        } else if build == "env_num" && self.peek() == Some("(") {
+            self.make_token(Token::Fn(Function::EnvNum))
+        } else if build == "env_bool" && self.peek() == Some("(") {
+            self.make_token(Token::Fn(Function::EnvBool))
         } else {
             while let Some(v) = self.peek() {
-                if !Self::is_alpha(v) && !Self::is_digit(v) {
+                if (!Self::is_alpha(v) && !Self::is_digit(v)) || (self.binding && v == "=") {
                     break;
                 }
                 self.eat();
             }
 
             let build = self.current_string_read[0..].to_string();
+            if self.options.native_fns.contains_key(&build) && self.peek() == Some("(") {
+                return self.make_token(Token::Fn(Function::Native(build)));
+            }
             self.make_token(Token::Str(build.replace(r#"\\""#, r#"""#)))
         }
     }
 
-    fn macro_t(&mut self) -> Result<TokenData, String> {
+    fn macro_t(&mut self) -> Result<TokenData, EconError> {
+        let invocation_start = self.token_start;
         self.eat();
 
+        if !self.options.macros {
+            return self.error("Macros are disabled.".to_string());
+        }
+
         if let Token::Str(s) = (self.keyword()?).token {
-            let macro_obj = if let Some(m) = self.macros.get(&s) {
-                Some(m.clone())
-            } else {
-                None
-            };
+            if s == "@include" {
+                return self.include();
+            }
+
+            let macro_obj = self.macros.get(&s).cloned();
 
             if let Some(m) = macro_obj {
                 if let Some("(") = self.peek() {
@@ -399,12 +515,7 @@ impl<'a> EconLexer<'a> {
                                 } => {
                                     depth -= 1;
                                     if depth <= 0 {
-                                        let mut tmp = vec![];
-                                        for i in current_group {
-                                            tmp.push(i);
-                                        }
-                                        groupings.push(tmp);
-                                        current_group = vec![];
+                                        groupings.push(current_group);
                                         break;
                                     }
                                 }
@@ -418,6 +529,9 @@ impl<'a> EconLexer<'a> {
                         }
                     }
 
+                    self.token_start = invocation_start;
+                    let invocation = self.span_from(invocation_start);
+
                     if m.0.len() != groupings.len() {
                         self.error(format!(
                             "{} of {} args supplied to {}.",
@@ -449,10 +563,16 @@ impl<'a> EconLexer<'a> {
                                         }
                                     }
 
-                                    new_stream.push(i);
+                                    new_stream.push(TokenData {
+                                        span: invocation,
+                                        ..i
+                                    });
                                 }
                                 _ => {
-                                    new_stream.push(i);
+                                    new_stream.push(TokenData {
+                                        span: invocation,
+                                        ..i
+                                    });
                                 }
                             }
                         }
@@ -507,7 +627,7 @@ impl<'a> EconLexer<'a> {
                             self.start = self.current;
                         }
 
-                        if let Some("\n") = self.peek() {
+                        if let Some("\n") | Some("\r\n") | None = self.peek() {
                             break;
                         }
 
@@ -516,8 +636,8 @@ impl<'a> EconLexer<'a> {
 
                     self.macros.insert(s.clone(), (params, stream));
 
-                    //a somewhat hacky way of telling the lexer to process the macro
-                    Err("Macro".to_string())
+                    //a definition emits no tokens of its own
+                    self.make_token(Token::Macro(vec![]))
                 } else {
                     self.error(format!("Expect '(' after Macro {}.", s))
                 }
@@ -527,9 +647,42 @@ impl<'a> EconLexer<'a> {
         }
     }
 
+    /// `@include("file")`: lex another file and bring its macro definitions into scope. Emits no tokens.
+    fn include(&mut self) -> Result<TokenData, EconError> {
+        if !self.options.includes {
+            return self.error("Includes are disabled.".to_string());
+        }
+
+        let target = match (self.scan()?.token, self.scan()?.token) {
+            (Token::LeftParen, Token::Str(target)) => target,
+            _ => return self.error("Expect '(\"file\")' after include.".to_string()),
+        };
+        if self.scan()?.token != Token::RightParen {
+            return self.error("Expect ')' after include file.".to_string());
+        }
+
+        let from = Path::new(self.options.source_name.as_deref().unwrap_or(""));
+        let (id, source) = match self.options.resolver.resolve(from, &target) {
+            Ok(file) => file,
+            Err(e) => return self.error(format!("Cannot include '{}': {}.", target, e.message())),
+        };
+        if let Some(cycle) = import::cycle(&self.options.including, &id) {
+            return self.error(format!("Include cycle: {}.", cycle));
+        }
+
+        let mut options = self.options.clone().source_name(id.as_str());
+        options.including.push(id);
+
+        let mut lexer = EconLexer::with_options(&source, options);
+        while lexer.scan()?.token != Token::EOF {}
+        self.macros.extend(lexer.macros);
+
+        self.make_token(Token::Macro(vec![]))
+    }
+
     fn is_alpha(c: &str) -> bool {
-        (c >= "a" && c <= "z")
-            || (c >= "A" && c <= "Z")
+        ("a"..="z").contains(&c)
+            || ("A"..="Z").contains(&c)
             || c == "_"
             || c == "'"
             || c == "!"
@@ -542,10 +695,25 @@ impl<'a> EconLexer<'a> {
             || c == ">"
     }
 
-    pub fn scan(&mut self) -> Result<TokenData, String> {
+    /// Make sure lexing moves on after `scan` returned an error so the rest of the source can still be read.
+    pub fn synchronize(&mut self) {
+        if self.current == self.token_start && !self.at_end() {
+            self.eat();
+        }
+    }
+
+    pub fn scan(&mut self) -> Result<TokenData, EconError> {
+        let token = self.token();
+        // the `let` keyword as written, not a string literal holding it
+        self.binding = matches!(&token, Ok(t) if t.token == Token::Str("let".to_string()) && &self.source[t.span.start..t.span.end] == "let");
+        token
+    }
+
+    fn token(&mut self) -> Result<TokenData, EconError> {
         self.skip_whitespace()?;
         self.current_string_read = String::from("");
         self.start = self.current;
+        self.token_start = self.current;
 
         if self.at_end() {
             self.make_token(Token::EOF)
@@ -578,7 +746,7 @@ impl<'a> EconLexer<'a> {
                         self.eat();
                         self.make_token(Token::Arrow)
                     }
-                    _ => self.error("Unexpected Token.".to_string()),
+                    _ => self.make_token(Token::Assign),
                 },
                 Some("&") => {
                     if let Some("&") = self.peek() {
@@ -634,7 +802,12 @@ impl<'a> EconLexer<'a> {
                     if Self::is_digit(v) {
                         self.number()
                     } else if Self::is_alpha(v) {
-                        self.keyword()
+                        match self.keyword()? {
+                            TokenData { token: Token::Fn(f), .. } if !self.options.functions => {
+                                self.error(format!("Functions are disabled got '{}'.", f.name()))
+                            }
+                            token => Ok(token),
+                        }
                     } else {
                         let v2 = self.peek();
                         self.error(format!("Unexpected Token got {:?}.", v2))
@@ -1,14 +1,32 @@
+pub mod ast;
+mod cmp;
+pub mod de;
 pub mod econ;
+pub mod error;
+pub mod evaluator;
+pub mod import;
+pub mod iter;
+pub mod json;
 pub mod lexer;
+mod macros;
 pub mod object;
+pub mod options;
 pub mod parser;
+pub mod path;
+pub mod ser;
 pub mod value;
 
+pub use de::from_value;
+pub use ser::to_value;
+
 #[cfg(test)]
 mod tests {
     use std::fmt::Debug;
 
     use econ::Econ;
+    use error::{EconError, ErrorKind};
+    use object::EconObj;
+    use value::EconValue;
     use serde::{Deserialize, Serialize};
 
     use super::*;
@@ -25,7 +43,7 @@ mod tests {
         "#,
             true,
         );
-        assert_eq!(true, matches!(obj, Ok(_)));
+        assert!(obj.is_ok());
     }
 
     #[test]
@@ -43,10 +61,7 @@ mod tests {
 
     #[test]
     fn simple_from_file() {
-        assert_eq!(
-            true,
-            matches!(Econ::create("test/Simple.econ", true), Ok(_))
-        );
+        assert!(Econ::create("test/Simple.econ", true).is_ok());
     }
 
     #[test]
@@ -88,27 +103,37 @@ mod tests {
             true,
         );
 
-        assert_eq!(true, matches!(obj, Ok(_)));
+        assert!(obj.is_ok());
     }
 
     #[test]
     fn complex_from_file() {
         //let a: serde_json::Value = serde_json::from_str(&fs::read_to_string("test/Complex.econ").unwrap()).expect("JSON was not well-formatted");
         //println!("{}", a);
-        assert_eq!(
-            true,
-            matches!(Econ::create("test/Complex.econ", true), Ok(_))
-        );
+        assert!(Econ::create("test/Complex.econ", true).is_ok());
     }
 
     #[test]
     fn large_from_file() {
-        //let a: serde_json::Value = serde_json::from_str(&fs::read_to_string("test/large-file.json").unwrap()).expect("JSON was not well-formatted");
-        //println!("{}", a);
-        assert_eq!(
-            true,
-            matches!(Econ::create("test/large-file.json", false), Ok(_))
-        );
+        let events: Vec<serde_json::Value> = (0..2000).map(|i| serde_json::json!({
+            "id": (2489651045u64 + i).to_string(),
+            "type": if i % 3 == 0 { "WatchEvent" } else { "PushEvent" },
+            "actor": { "id": 665991 + i, "login": format!("user{}", i), "gravatar_id": "", "url": format!("https://api.github.com/users/user{}", i) },
+            "repo": { "id": 28688495 + i, "name": format!("org{}/repo{}", i % 50, i), "url": format!("https://api.github.com/repos/org{}/repo{}", i % 50, i) },
+            "payload": { "ref": "master", "ref_type": "branch", "size": i % 4, "distinct": i % 2 == 0, "commits": [] },
+            "public": i % 5 != 0,
+            "score": i as f64 * 0.75 + 0.04,
+            "created_at": "2015-01-01T15:00:00Z",
+            "org": { "id": 9285252 + i, "login": format!("org{}", i % 50) }
+        })).collect();
+        let path = std::env::temp_dir().join(format!("econ_rs_large_from_file_{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string_pretty(&events).unwrap()).unwrap();
+
+        let obj = Econ::from_path(&path, false);
+        std::fs::remove_file(&path).unwrap();
+        let obj = obj.unwrap();
+        assert_eq!(Some(2000), obj.as_array().map(Vec::len));
+        assert_eq!(Some("user1999"), obj[1999]["actor"]["login"].as_str());
     }
 
     #[test]
@@ -123,7 +148,7 @@ mod tests {
             true,
         );
 
-        assert_eq!(true, matches!(obj, Ok(_)));
+        assert!(obj.is_ok());
     }
 
     #[test]
@@ -146,7 +171,7 @@ mod tests {
             true,
         );
 
-        assert_eq!(true, matches!(obj, Ok(_)));
+        assert!(obj.is_ok());
     }
 
     #[test]
@@ -182,7 +207,191 @@ mod tests {
             true,
         );
 
-        assert_eq!(true, matches!(obj, Err(_)));
+        assert!(matches!(obj, Err(EconError::Constraint(_))));
+        assert_eq!("No Hello Worlds!", obj.unwrap_err().message());
+    }
+
+    #[test]
+    fn error_kinds() {
+        let type_err = Econ::create("{ a: true + 1 }", false).unwrap_err();
+        assert_eq!(ErrorKind::Type, type_err.kind());
+
+        let parse_err = Econ::create("{ a: 1 b: 2 }", false).unwrap_err();
+        assert_eq!(ErrorKind::Parse, parse_err.kind());
+        assert!(parse_err.to_string().contains("Error Parsing"));
+
+        let lex_err = Econ::create("{ a: \"unterminated }", false).unwrap_err();
+        assert_eq!(ErrorKind::Lex, lex_err.kind());
+    }
+
+    #[test]
+    fn error_spans() {
+        let src = "{\n\ta: 1, b: true + 2, c: 3\n}";
+        let err = Econ::create(src, false).unwrap_err();
+        let span = err.span().unwrap();
+
+        assert_eq!((1, 10), (span.line, span.column));
+        assert_eq!("true + 2", &src[span.start..span.end]);
+
+        let src = "{\n  @p(n) n: 1 + true\n  @p(z)\n}";
+        let span = Econ::create(src, false).unwrap_err().span().unwrap();
+        assert_eq!("@p(z)", &src[span.start..span.end]);
+    }
+
+    #[test]
+    fn error_recovery() {
+        let (obj, errors) = Econ::create_recovering(
+            r#"
+        {
+            @!{number, x => $x > 100, "Too big"}
+            a: 1 + true,
+            b: 2,
+            c: [1, 2 3, 4],
+            d: { da: 1000, db: 2 },
+            e: "unterminated
+        "#,
+            false,
+        );
+
+        let kinds: Vec<ErrorKind> = errors.iter().map(|e| e.kind()).collect();
+        assert_eq!(
+            vec![ErrorKind::Type, ErrorKind::Parse, ErrorKind::Constraint, ErrorKind::Parse, ErrorKind::Lex],
+            kinds
+        );
+        assert_eq!(2f64, obj["b"].value::<f64>());
+        assert_eq!(4f64, obj["c"][2].value::<f64>());
+        assert_eq!(2f64, obj["d"]["db"].value::<f64>());
+        assert!(matches!(obj["a"], EconValue::Nil));
+    }
+
+    #[test]
+    fn ast_evaluate() {
+        let src = r#"
+        {
+            x: 10,
+            a: map([1, 2, 3], x => $x * 2),
+            b: $x,
+            c: fold(filter([], x => true), |x, acc| => $x + $acc)
+        }
+        "#;
+        let mut lexer = lexer::EconLexer::init(src);
+        let ast = parser::EconParser::new(src).parse_ast(&mut lexer).unwrap();
+        assert!(matches!(ast.kind, ast::ExprKind::Object(_)));
+
+        let mut evaluator = evaluator::EconEvaluator::new(src);
+        let first = evaluator.evaluate(&ast).unwrap();
+        let second = evaluator.evaluate(&ast).unwrap();
+
+        assert_eq!(6f64, first["a"][2].value::<f64>());
+        assert_eq!(10f64, first["b"].value::<f64>());
+        assert!(matches!(first["c"], EconValue::Nil));
+        assert_eq!(first.to_string(), second.to_string());
+    }
+
+    #[test]
+    fn forward_refs() {
+        let obj = Econ::create(
+            r#"
+        {
+            a: $b + 1,
+            b: 2,
+            c: { x: $$d.y },
+            d: { y: 7 }
+        }
+        "#,
+            false,
+        ).unwrap();
+        assert_eq!(3f64, obj["a"].value::<f64>());
+        assert_eq!(7f64, obj["c"]["x"].value::<f64>());
+
+        let err = Econ::create("{ a: $b, b: $c, c: $a }", false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert_eq!("Cycle detected: a -> b -> c -> a.", err.message());
+
+        // keys of objects set aside while forcing are not part of a cycle
+        let obj = Econ::create("{ a: { x: $$b }, b: { y: $x } }", false).unwrap();
+        assert!(obj["b"]["y"].is_nil());
+        let err = Econ::create_strict("{ a: { x: $$b }, b: { y: $x } }", false).unwrap_err();
+        assert_eq!("Unresolved reference '$x', searched root.b.", err.message());
+        let obj = Econ::create("{ a: { x: $$b.y }, b: { y: 1, z: $x } }", false).unwrap();
+        assert_eq!((Some(1f64), true), (obj["a"]["x"].as_f64(), obj["b"]["z"].is_nil()));
+
+        let chain = |n: usize| format!("{{ {}, a{}: 1 }}", (0..n).map(|i| format!("a{}: $a{}", i, i + 1)).collect::<Vec<_>>().join(", "), n);
+        assert!(Econ::create(&chain(8), options::EconOptions::new().max_depth(8)).is_ok());
+        let err = Econ::create(&chain(9), options::EconOptions::new().max_depth(8)).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert_eq!("References nest deeper than the maximum of 8.", err.message());
+        let err = Econ::create(&chain(5000), false).unwrap_err();
+        assert_eq!("References nest deeper than the maximum of 256.", err.message());
+    }
+
+    #[test]
+    fn strict_refs() {
+        let src = "{ name: 1, b: { c: !nmae } }";
+        assert!(matches!(Econ::create(src, false).unwrap()["b"]["c"], EconValue::Nil));
+
+        let err = Econ::create_strict(src, false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert_eq!("Unresolved reference '!nmae', searched root.b, root. Did you mean '!name'?", err.message());
+
+        let err = Econ::create_strict("{ a: { b: $$$a } }", false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert!(Econ::create_strict("{ a: nil, b: !a, c: map([1], x => $x) }", false).is_ok());
+    }
+
+    #[test]
+    fn parse_options() {
+        use std::sync::{Arc, Mutex};
+        use options::{EconOptions, Trace};
+
+        let lines = Arc::new(Mutex::new(vec!()));
+        let sink = lines.clone();
+        let options = EconOptions::new()
+            .trace(&[Trace::Value, Trace::Errors])
+            .trace_to(move |line| sink.lock().unwrap().push(line.to_string()));
+
+        assert!(Econ::create("{ a: 1 }", options.clone()).is_ok());
+        assert!(Econ::create("{ a: 1 b: 2 }", options).is_err());
+        let lines = lines.lock().unwrap();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with("----Eval----"));
+        assert!(lines[1].contains("Error Parsing"));
+
+        assert!(Econ::create("{ a: 1, a: 2 }", false).is_err());
+        assert!(Econ::create("{ a: 1, a: 2 }", EconOptions::new().allow_duplicate_keys(true)).is_ok());
+
+        let err = Econ::create("{ @m(x) x: 1 }", EconOptions::new().macros(false)).unwrap_err();
+        assert_eq!(ErrorKind::Lex, err.kind());
+        let err = Econ::create("{ a: keys({}) }", EconOptions::new().functions(false)).unwrap_err();
+        assert_eq!(ErrorKind::Lex, err.kind());
+
+        assert!(Econ::create("{ a: [[1]] }", EconOptions::new().max_depth(3)).is_ok());
+        let err = Econ::create("{ a: [[(1)]] }", EconOptions::new().max_depth(3)).unwrap_err();
+        assert_eq!("Nesting exceeds the maximum depth of 3.", err.message());
+        assert!(Econ::create("{ a: 1 }", EconOptions::new().max_source_len(4)).is_err());
+    }
+
+    #[test]
+    fn explicit_sources() {
+        let path = std::env::temp_dir().join(format!("econ_rs_explicit_sources_{}.econ", std::process::id()));
+        std::fs::write(&path, "{ a: 1, b: $a + true }").unwrap();
+
+        let err = Econ::from_path(&path, false).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(ErrorKind::Type, err.kind());
+        assert_eq!(Some(path.display().to_string().as_str()), err.file());
+        assert!(err.to_string().starts_with(&format!("{}: Line", path.display())));
+
+        let err = Econ::from_path(&path, false).unwrap_err();
+        assert_eq!(ErrorKind::Io, err.kind());
+        assert_eq!(Some(path.display().to_string().as_str()), err.file());
+
+        let err = Econ::from_str(path.to_str().unwrap(), false).unwrap_err();
+        assert_ne!(ErrorKind::Io, err.kind());
+        assert_eq!(None, err.file());
+
+        let obj = Econ::from_reader("{ a: [1, 2] }".as_bytes(), false).unwrap();
+        assert_eq!(2f64, obj["a"][1].value::<f64>());
     }
 
     #[test]
@@ -214,7 +423,6 @@ mod tests {
             y: f64,
         }
 
-        let p;
         let obj = Econ::from(
             r#"
         {
@@ -223,7 +431,793 @@ mod tests {
         }
         "#,
         );
-        p = Econ::to_struct::<Point>(&obj).unwrap();
+        let p = Econ::to_struct::<Point>(&obj).unwrap();
         assert_eq!(Point { x: 2.0, y: 7.0 }, p);
     }
-}
+
+    #[test]
+    fn econ_deserialize_direct() {
+        use std::collections::HashMap;
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Shape {
+            Empty,
+            Circle(f64),
+            Rect { w: u32, h: u32 },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config<'a> {
+            name: &'a str,
+            quote: String,
+            shapes: Vec<Shape>,
+            limits: HashMap<String, u8>,
+            pair: (i32, bool),
+            missing: Option<u8>,
+        }
+
+        let obj = Econ::from(
+            r#"
+        {
+            name: "conf",
+            quote: "say \"hi\"" \
+                   "bye",
+            shapes: ["Empty", { Circle: 1.5 }, { Rect: { w: 2 * 2, h: 3 } }],
+            limits: { a: 1, b: 255 },
+            pair: [-7, true],
+            missing: nil
+        }
+        "#,
+        );
+        let config: Config = de::from_value(&obj).unwrap();
+        assert_eq!("conf", config.name);
+        assert_eq!("say \"hi\"\nbye", config.quote);
+        assert_eq!(vec![Shape::Empty, Shape::Circle(1.5), Shape::Rect { w: 4, h: 3 }], config.shapes);
+        assert_eq!(Some(&255), config.limits.get("b"));
+        assert_eq!((-7, true), config.pair);
+        assert_eq!(None, config.missing);
+
+        let obj = Econ::from("{ a: { b: [1, 2, { c: 1.5 }] } }");
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct C {
+            c: u32,
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct B {
+            b: (u32, u32, C),
+        }
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct A {
+            a: B,
+        }
+        match Econ::to_struct::<A>(&obj).unwrap_err() {
+            EconError::Deserialize { path, .. } => assert_eq!("a.b[2].c", path),
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn econ_serialize() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        enum Mode {
+            Off,
+            Level(u8),
+            Range { lo: i32, hi: i32 },
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Defaults {
+            name: String,
+            ratio: f32,
+            modes: Vec<Mode>,
+            by_id: BTreeMap<u32, bool>,
+            extra: Option<String>,
+        }
+
+        let defaults = Defaults {
+            name: "x".to_string(),
+            ratio: 0.5,
+            modes: vec![Mode::Off, Mode::Level(3), Mode::Range { lo: -1, hi: 1 }],
+            by_id: BTreeMap::from([(1, true), (20, false)]),
+            extra: None,
+        };
+
+        let value = to_value(&defaults).unwrap();
+        assert_eq!(3f64, value["modes"][1]["Level"].value::<f64>());
+        assert!(matches!(value["by_id"]["20"], EconValue::Bool(false)));
+        assert!(matches!(value["extra"], EconValue::Nil));
+        assert_eq!(defaults, from_value::<Defaults>(&value).unwrap());
+
+        let obj = Econ::from("{ a: [1, { b: \"c\" }], d: nil }");
+        assert_eq!(r#"{"a":[1,{"b":"c"}],"d":null}"#, serde_json::to_string(&obj).unwrap());
+
+        let err = to_value(&BTreeMap::from([("k", BTreeMap::from([((1, 2), 3)]))])).unwrap_err();
+        assert_eq!(ErrorKind::Serialize, err.kind());
+        assert!(matches!(err, EconError::Serialize { ref path, .. } if path == "k"));
+    }
+
+    #[test]
+    fn json_output() {
+        use json::{JsonOptions, NonFinite};
+
+        let obj = Econ::from(
+            r#"
+        {
+            b: [[1, 2], [], {}],
+            a: "tab\there \"quoted\"" \
+               "second line",
+            c: { z: 1 / 0 }
+        }
+        "#,
+        );
+
+        let parsed: serde_json::Value = serde_json::from_str(&obj.to_string()).unwrap();
+        assert_eq!("tab\\there \"quoted\"\nsecond line", parsed["a"]);
+        assert_eq!(serde_json::Value::Null, parsed["c"]["z"]);
+        assert!(obj["b"].to_string().starts_with("[\n\t[\n\t\t1,"));
+
+        let compact = JsonOptions::new().compact().sort_keys(true).non_finite(NonFinite::String);
+        assert_eq!(
+            r#"{"a":"tab\\there \"quoted\"\nsecond line","b":[[1,2],[],{}],"c":{"z":"inf"}}"#,
+            obj.to_json(&compact).unwrap()
+        );
+        assert_eq!("{\n  \"z\": null\n}", obj["c"].to_json(&JsonOptions::new().indent("  ").non_finite(NonFinite::Null)).unwrap());
+
+        let err = obj.to_json(&JsonOptions::new()).unwrap_err();
+        assert!(matches!(err, EconError::Serialize { ref path, .. } if path == "c.z"));
+    }
+
+    #[test]
+    fn canonical_json() {
+        let a = Econ::from("{ z: { y: 1, x: [0.5, -0] }, \"\u{ff61}\": 1000000000000000000000, \"\u{1F600}\": 2, b: 100 }");
+        let b = Econ::from("{ b: 10 * 10, \"\u{1F600}\": 1 + 1, \"\u{ff61}\": 10 * 100000000000000000000, z: { x: [1 / 2, 0], y: 1.0 } }");
+        assert_eq!(a.to_canonical_json().unwrap(), b.to_canonical_json().unwrap());
+        assert_eq!(
+            "{\"b\":100,\"z\":{\"x\":[0.5,0],\"y\":1},\"\u{1F600}\":2,\"\u{ff61}\":1e+21}",
+            a.to_canonical_json().unwrap()
+        );
+
+        let numbers = EconValue::Arr(
+            [1e-7, 0.000001, 123456789012345680000.0, 4.5, 2e-3, 1.7976931348623157e308, 5e-324, -1.5e-9, 333333333.3333333]
+                .into_iter()
+                .map(EconValue::Num)
+                .collect(),
+        );
+        assert_eq!(
+            "[1e-7,0.000001,123456789012345680000,4.5,0.002,1.7976931348623157e+308,5e-324,-1.5e-9,333333333.3333333]",
+            numbers.to_canonical_json().unwrap()
+        );
+        assert!(EconValue::Num(f64::NAN).to_canonical_json().is_err());
+    }
+
+    #[test]
+    fn integers() {
+        let obj = Econ::from(
+            r#"
+        {
+            id: 9007199254740993,
+            next: $id + 1,
+            half: 5 / 2,
+            exact: 6 / 3,
+            mixed: 1 + 0.5,
+            rem: -7 % 3,
+            len: #[1, 2, 3],
+            same: 2 == 2.0,
+            huge: 99999999999999999999
+        }
+        "#,
+        );
+
+        assert!(matches!(obj["next"], EconValue::Int(9007199254740994)));
+        assert!(!matches!(obj["half"], EconValue::Int(_)));
+        assert_eq!(2.5, obj["half"].value::<f64>());
+        assert!(matches!(obj["exact"], EconValue::Int(2)));
+        assert!(!matches!(obj["mixed"], EconValue::Int(_)));
+        assert_eq!(1.5, obj["mixed"].value::<f64>());
+        assert!(matches!(obj["rem"], EconValue::Int(2)));
+        assert!(matches!(obj["len"], EconValue::Int(3)));
+        assert!(matches!(obj["same"], EconValue::Bool(true)));
+        assert!(!matches!(obj["huge"], EconValue::Int(_)));
+        assert_eq!(9007199254740993u64, obj["id"].value::<u64>());
+        assert!(obj.to_string().contains("\"id\": 9007199254740993,"));
+        assert_eq!(9007199254740993u64, from_value::<u64>(&obj["id"]).unwrap());
+        assert!(from_value::<u8>(&obj["id"]).is_err());
+        assert!(matches!(to_value(&u64::MAX).unwrap(), EconValue::Num(_)));
+        assert!(matches!(to_value(&i64::MIN).unwrap(), EconValue::Int(i64::MIN)));
+
+        let err = Econ::create("{ a: 9223372036854775807 + 1 }", false).unwrap_err();
+        assert_eq!(ErrorKind::Type, err.kind());
+        assert!(err.message().starts_with("Integer overflow in '+'"));
+    }
+
+    #[test]
+    fn try_values() {
+        let obj = Econ::from("{ port: 70000, neg: -1, half: 2.5, whole: 4.0, name: \"a\", ok: true, arr: [1, 2], o: { k: 1 } }");
+
+        assert_eq!(70000u32, obj["port"].try_value::<u32>().unwrap());
+        assert!(matches!(obj["port"].try_value::<u16>(), Err(EconError::Deserialize { .. })));
+        assert!(obj["neg"].try_value::<u64>().is_err());
+        assert_eq!(-1i8, obj["neg"].try_value::<i8>().unwrap());
+        assert!(obj["half"].try_value::<i64>().is_err());
+        assert_eq!(4i64, obj["whole"].try_value::<i64>().unwrap());
+        assert_eq!(2.5f64, obj["half"].try_value::<f64>().unwrap());
+        assert!(obj["name"].try_value::<f64>().is_err());
+        assert!(obj["missing"].try_value::<String>().is_err());
+        assert_eq!("a", obj["name"].try_value::<String>().unwrap());
+        assert!(obj["ok"].try_value::<bool>().unwrap());
+
+        // value keeps converting like `as` with a fallback, for these types and a caller's own From impls
+        assert_eq!(2i64, obj["half"].value::<i64>());
+        assert_eq!(70000u32 as u16, obj["port"].value::<u16>());
+        assert_eq!("Undefined", obj["ok"].value::<String>());
+        assert_eq!(0f64, obj["missing"].value::<f64>());
+        struct Port(u16);
+        impl From<&EconValue> for Port {
+            fn from(item: &EconValue) -> Self {
+                Port(item.try_value().unwrap_or(80))
+            }
+        }
+        assert_eq!(80, obj["port"].value::<Port>().0);
+        assert_eq!(u32::try_from(obj["port"].clone()).ok(), Some(70000));
+
+        assert_eq!(Some("a"), obj["name"].as_str());
+        assert_eq!(None, obj["ok"].as_str());
+        assert_eq!(Some(2.5), obj["half"].as_f64());
+        assert_eq!(Some(4), obj["whole"].as_i64());
+        assert_eq!(Some(true), obj["ok"].as_bool());
+        assert_eq!(2, obj["arr"].as_array().unwrap().len());
+        assert!(obj["o"].as_object().is_some());
+        assert!(obj["arr"].as_object().is_none());
+        assert!(obj["missing"].is_nil());
+        assert!(!obj["o"].is_nil());
+    }
+
+    #[test]
+    fn paths() {
+        let mut obj = Econ::from("{ a: { b: { c: [1, 2, { d: true }] } }, \"x.y\": 1, \"m/n~\": 2 }");
+
+        assert_eq!(Some(2f64), obj.pointer("/a/b/c/1").and_then(|v| v.as_f64()));
+        assert_eq!(Some(2f64), obj.pointer("/m~1n~0").and_then(|v| v.as_f64()));
+        assert!(obj.pointer("").is_some_and(|v| v.as_object().is_some()));
+        assert!(obj.pointer("/a/b/c/01").is_none());
+        assert!(obj.pointer("a").is_none());
+
+        assert_eq!(Some(true), obj.path("a.b.c[2].d").unwrap().as_bool());
+        assert_eq!(Some(2f64), obj.path("a.b.c.1").unwrap().as_f64());
+        assert_eq!(Some(1f64), obj.path("[\"x.y\"]").unwrap().as_f64());
+
+        let err = obj.path("a.b.c[5]").unwrap_err();
+        assert_eq!(ErrorKind::Path, err.kind());
+        assert!(matches!(&err, EconError::Path { path, .. } if path == "a.b.c[5]"));
+        assert_eq!("Index 5 is out of bounds for an array of 3.", err.message());
+        assert!(matches!(obj.path("a.b.c[2].d.e"), Err(EconError::Path { path, .. }) if path == "a.b.c[2].d.e"));
+        assert!(matches!(obj.path("a..b"), Err(EconError::Path { path, .. }) if path == "a."));
+        assert!(obj.path("a[").is_err());
+
+        *obj.path_mut("a.b.c[0]").unwrap() = EconValue::from("one");
+        *obj.pointer_mut("/a/b/c/2/d").unwrap() = EconValue::Bool(false);
+        assert_eq!(Some("one"), obj["a"]["b"]["c"][0].as_str());
+        assert_eq!(Some(false), obj["a"]["b"]["c"][2]["d"].as_bool());
+        assert!(obj.path_mut("a.z").is_err());
+    }
+
+    #[test]
+    fn editing() {
+        let mut obj = Econ::from("{ a: 1, b: [1], c: { d: true }, e: 2 }");
+
+        assert_eq!(Some(1f64), obj.insert("a", 5).unwrap().and_then(|v| v.as_f64()));
+        obj["b"].push(2).unwrap();
+        assert!(obj["a"].push(2).is_err());
+        assert!(matches!(obj["c"]["d"].insert("x", 1), Err(EconError::Path { path, .. }) if path == "x"));
+        assert_eq!(Some(true), obj.remove("c").and_then(|c| c["d"].as_bool()));
+        assert!(obj.remove("c").is_none());
+
+        obj["new"]["deep"]["key"] = EconValue::from("v");
+        assert_eq!(Some("v"), obj.path("new.deep.key").unwrap().as_str());
+        obj["list"].push("x").unwrap();
+        assert_eq!(1, obj["list"].as_array().unwrap().len());
+
+        // mutable indexing never panics, it replaces what's in the way and pads arrays
+        obj["list"][3] = EconValue::from(4);
+        assert_eq!(Econ::from(r#"{ l: ["x", nil, nil, 4] }"#)["l"], obj["list"]);
+        obj["count"]["n"] = EconValue::from(1);
+        assert_eq!(Some(1), obj.path("count.n").unwrap().as_i64());
+        obj["count"][1] = EconValue::from(2);
+        assert_eq!(Econ::from("{ l: [nil, 2] }")["l"], obj["count"]);
+        obj.remove("count");
+
+        *obj.get_or_insert_with("count", || EconValue::from(0)).unwrap() = EconValue::from(3);
+        obj.get_or_insert_with("count", || EconValue::from(9));
+        assert_eq!(Some(3), obj["count"].as_i64());
+        obj.entry("e").unwrap().and_modify(|v| *v = EconValue::from(7));
+        assert_eq!(Some(7), obj["e"].as_i64());
+
+        let taken = obj["b"].take();
+        assert_eq!(2, taken.as_array().unwrap().len());
+        assert!(obj["b"].is_nil());
+
+        obj.retain(|k, _| k != "new");
+        let keys: Vec<&String> = obj.as_object().unwrap().data.keys().collect();
+        assert_eq!(vec!["a", "b", "e", "list", "count"], keys);
+        assert!(Econ::from("{ s: 1 }")["missing"].is_nil());
+
+        let mut o = EconObj::new();
+        o.insert("x", 1);
+        o.insert("y", 2);
+        assert_eq!(Some(1), o.take("x").as_i64());
+        assert!(o.take("x").is_nil());
+        assert!(o.remove("x").is_some());
+        assert_eq!(1, o.len());
+    }
+
+    #[test]
+    fn comparison() {
+        use std::collections::{BTreeSet, HashSet};
+
+        assert_eq!(Econ::from("{ a: 1, b: [true, \"x\"] }"), Econ::from("{ b: [true, \"x\"], a: 1.0 }"));
+        assert_ne!(Econ::from("{ a: 1 }"), Econ::from("{ a: 1, b: nil }"));
+        assert_ne!(Econ::from("[1, 2]"), Econ::from("[2, 1]"));
+        assert_eq!(EconValue::Num(f64::NAN), EconValue::Num(f64::NAN));
+        assert_eq!(EconValue::Num(-0.0), EconValue::Int(0));
+        assert_ne!(EconValue::Int(9007199254740993), EconValue::Num(9007199254740992.0));
+        assert!(EconValue::Int(9007199254740993) > EconValue::Num(9007199254740992.0));
+        assert!(EconValue::Int(2) < EconValue::Num(2.5));
+        assert!(EconValue::Num(f64::INFINITY) < EconValue::Num(f64::NAN));
+
+        let mut values = vec![
+            EconValue::from("b"),
+            EconValue::Num(f64::NAN),
+            EconValue::from(2),
+            EconValue::Bool(true),
+            EconValue::Nil,
+            EconValue::Num(-1.5),
+            EconValue::from("a"),
+        ];
+        values.sort();
+        assert_eq!(vec![
+            EconValue::Nil,
+            EconValue::Bool(true),
+            EconValue::Num(-1.5),
+            EconValue::from(2),
+            EconValue::Num(f64::NAN),
+            EconValue::from("a"),
+            EconValue::from("b"),
+        ], values);
+
+        let set: HashSet<EconValue> = [EconValue::Int(1), EconValue::Num(1.0), EconValue::Num(f64::NAN), EconValue::Num(f64::NAN)].into_iter().collect();
+        assert_eq!(2, set.len());
+        let set: HashSet<EconValue> = [Econ::from("{ a: 1, b: 2 }"), Econ::from("{ b: 2, a: 1 }")].into_iter().collect();
+        assert_eq!(1, set.len());
+        let set: BTreeSet<EconValue> = [Econ::from("{ a: 2 }"), Econ::from("{ a: 1 }"), Econ::from("{ a: 1.0 }")].into_iter().collect();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn iterators() {
+        let mut obj = Econ::from("{ a: [1, 2, 3], b: { c: \"x\", \"d.e\": [true], \"\": nil }, f: 4 }");
+
+        assert_eq!(6f64, obj["a"].iter().filter_map(|v| v.as_f64()).sum::<f64>());
+        assert_eq!(vec!["a", "b", "f"], obj.keys().collect::<Vec<_>>());
+        assert_eq!(3, obj["b"].entries().count());
+        assert_eq!(0, obj["f"].iter().count());
+        assert_eq!(0, obj["f"].keys().count());
+
+        for v in &mut obj["a"] {
+            *v = EconValue::from(v.as_i64().unwrap_or_default() * 10);
+        }
+        assert_eq!(Econ::from("[10, 20, 30]"), obj["a"]);
+
+        let paths: Vec<String> = obj.walk().map(|(path, _)| path).collect();
+        assert_eq!(vec!["", "a", "a[0]", "a[1]", "a[2]", "b", "b.c", "b[\"d.e\"]", "b[\"d.e\"][0]", "b[\"\"]", "f"], paths);
+        for (path, value) in obj.walk() {
+            assert_eq!(value, obj.path(&path).unwrap());
+        }
+
+        let EconValue::Obj(o) = obj.clone() else { panic!() };
+        assert_eq!(vec!["a", "b", "f"], o.keys().collect::<Vec<_>>());
+        let rebuilt: EconObj = o.into_iter().filter(|(k, _)| k != "b").collect();
+        assert_eq!(Econ::from("{ a: [10, 20, 30], f: 4 }"), EconValue::Obj(rebuilt));
+
+        let doubled: EconValue = obj["a"].clone().into_iter().map(|v| EconValue::from(v.as_i64().unwrap_or_default() * 2)).collect();
+        assert_eq!(Econ::from("[20, 40, 60]"), doubled);
+    }
+
+    #[test]
+    fn econ_macro() {
+        let key = String::from("computed");
+        let list = vec![1, 2];
+        let nested = econ!({ inner: true });
+        let value = econ!({
+            "a": 1,
+            b: [true, nil, "x", [], {}],
+            (key.as_str()): list,
+            c: { d: 1.5 + 1.0, e: null, f: Some("s"), g: None::<i32> },
+            h: nested,
+        });
+
+        assert_eq!(Econ::from(r#"{ a: 1, b: [true, nil, "x", [], {}], computed: [1, 2], c: { d: 2.5, e: nil, f: "s", g: nil }, h: { inner: true } }"#), value);
+        assert_eq!(EconValue::Nil, econ!(nil));
+        assert_eq!(EconValue::from(-3), econ!(-3));
+        assert_eq!(Econ::from("[[1, [2]], { a: [] }]"), econ!([[1, [2]], { a: [] }]));
+    }
+
+    #[test]
+    fn native_functions() {
+        use options::EconOptions;
+
+        let options = || EconOptions::new()
+            .register_fn("lookup_region", 1, |args| match args[0].as_str() {
+                Some("us-east") => Ok(EconValue::from("use1")),
+                Some(r) => Err(EconError::Deserialize { path: String::new(), message: format!("Unknown region {}.", r) }),
+                None => Ok(EconValue::Nil),
+            })
+            .register_fn("add", 2, |args| Ok(EconValue::from(args[0].try_value::<i64>()? + args[1].try_value::<i64>()?)));
+
+        let obj = Econ::create(r#"{ region: lookup_region("us-east"), sum: add(1, add(2, 3)), add: 1, list: map([1, 2], x => add($x, $add)) }"#, options()).unwrap();
+        assert_eq!(Some("use1"), obj["region"].as_str());
+        assert_eq!(Some(6), obj["sum"].as_i64());
+        assert_eq!(Some(1), obj["add"].as_i64());
+        assert_eq!(Econ::from("[2, 3]"), obj["list"]);
+
+        let err = Econ::create(r#"{ r: lookup_region("mars") }"#, options()).unwrap_err();
+        assert_eq!(ErrorKind::Type, err.kind());
+        assert_eq!("lookup_region: Unknown region mars.", err.message());
+        assert!(err.span().is_some());
+
+        let err = Econ::create("{ s: add(1) }", options()).unwrap_err();
+        assert_eq!("add: Expected 2 arguments got 1.", err.message());
+        let err = Econ::create("{ s: add(1, \"x\") }", options()).unwrap_err();
+        assert_eq!(ErrorKind::Type, err.kind());
+
+        assert!(Econ::create("{ s: add(1, 2) }", EconOptions::new()).is_err());
+    }
+
+    #[test]
+    fn host_vars() {
+        use options::EconOptions;
+
+        let options = || EconOptions::new()
+            .with_var("ctx", econ!({ env: "prod", zones: ["a", "b"] }))
+            .with_vars([("debug", false)]);
+
+        let obj = Econ::create(r#"{
+            replicas: !ctx.env == "prod" ? 5 : 1,
+            zone: !ctx.zones[1],
+            inner: { flag: !debug },
+            shadow: { ctx: 1, v: !ctx },
+            unset: { debug: nil, v: !debug },
+            local: $ctx
+        }"#, options()).unwrap();
+        assert_eq!(Some(5), obj["replicas"].as_i64());
+        assert_eq!(Some("b"), obj["zone"].as_str());
+        assert_eq!(Some(false), obj["inner"]["flag"].as_bool());
+        assert_eq!(Some(1), obj["shadow"]["v"].as_i64());
+        assert!(obj["unset"]["v"].is_nil());
+        assert!(obj["local"].is_nil());
+        assert!(Econ::create("{ ctx: nil, b: !ctx }", options()).unwrap()["b"].is_nil());
+
+        assert!(Econ::create("{ a: !ctx }", options().strict_refs(true)).is_ok());
+        assert!(Econ::create("{ a: $ctx }", options().strict_refs(true)).is_err());
+        assert!(Econ::create("{ a: !ctx }", EconOptions::new()).unwrap()["a"].is_nil());
+    }
+
+    #[test]
+    fn environment() {
+        use options::EconOptions;
+
+        std::env::set_var("ECON_TEST_URL", "postgres://db");
+        std::env::set_var("ECON_TEST_PORT", "5432");
+        std::env::set_var("ECON_TEST_RATIO", "0.5");
+        std::env::set_var("ECON_TEST_FLAG", "Yes");
+        std::env::remove_var("ECON_TEST_MISSING");
+
+        let obj = Econ::create(r#"{
+            url: env("ECON_TEST_URL"),
+            port: env_num("ECON_TEST_PORT") + 1,
+            ratio: env_num("ECON_TEST_RATIO"),
+            flag: env_bool("ECON_TEST_FLAG"),
+            fallback: env("ECON_TEST_MISSING", 8080),
+            env: "prod",
+            stage: $env
+        }"#, false).unwrap();
+        assert_eq!(Some("postgres://db"), obj["url"].as_str());
+        assert_eq!(Some(5433), obj["port"].as_i64());
+        assert_eq!(Some(0.5), obj["ratio"].as_f64());
+        assert_eq!(Some(true), obj["flag"].as_bool());
+        assert_eq!(Some(8080), obj["fallback"].as_i64());
+        assert_eq!(Some("prod"), obj["stage"].as_str());
+
+        let err = Econ::create(r#"{ a: env("ECON_TEST_MISSING") }"#, false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert_eq!("env: Environment variable 'ECON_TEST_MISSING' is not set.", err.message());
+        let err = Econ::create(r#"{ a: env_num("ECON_TEST_URL") }"#, false).unwrap_err();
+        assert_eq!(ErrorKind::Type, err.kind());
+        assert!(Econ::create(r#"{ a: env_bool("ECON_TEST_PORT") }"#, false).is_err());
+
+        let options = EconOptions::new().env_allowlist(["ECON_TEST_PORT"]);
+        assert!(Econ::create(r#"{ a: env("ECON_TEST_PORT") }"#, options.clone()).is_ok());
+        let err = Econ::create(r#"{ a: env("ECON_TEST_URL", "x") }"#, options).unwrap_err();
+        assert_eq!("env: Environment variable 'ECON_TEST_URL' is not accessible.", err.message());
+        assert!(Econ::create(r#"{ a: env("ECON_TEST_PORT") }"#, EconOptions::new().env(false)).is_err());
+    }
+
+    #[test]
+    fn imports() {
+        use options::EconOptions;
+
+        let dir = std::env::temp_dir().join(format!("econ_imports_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("shared")).unwrap();
+        std::fs::write(dir.join("shared/base.econ"), r#"{ region: "us-east", replicas: 3, limits: import("limits.econ") }"#).unwrap();
+        std::fs::write(dir.join("shared/limits.econ"), "{ cpu: 2 }").unwrap();
+        std::fs::write(dir.join("shared/macros.econ"), "@service(n, p) n: { port: p }\n").unwrap();
+        std::fs::write(dir.join("service.econ"), r#"{
+            @include("shared/macros.econ")
+            base: import("shared/base.econ"),
+            replicas: $base.replicas * 2,
+            services: { @service(api, 80), @service(web, 443) }
+        }"#).unwrap();
+        std::fs::write(dir.join("a.econ"), r#"{ b: import("b.econ") }"#).unwrap();
+        std::fs::write(dir.join("b.econ"), r#"{ a: import("a.econ") }"#).unwrap();
+        std::fs::write(dir.join("x.econ"), "@include(\"y.econ\")\n{}").unwrap();
+        std::fs::write(dir.join("y.econ"), "@include(\"x.econ\")\n").unwrap();
+
+        let options = EconOptions::new();
+        let obj = Econ::from_path(dir.join("service.econ"), options.clone()).unwrap();
+        assert_eq!(Some("us-east"), obj["base"]["region"].as_str());
+        assert_eq!(Some(2), obj["base"]["limits"]["cpu"].as_i64());
+        assert_eq!(Some(6), obj["replicas"].as_i64());
+        assert_eq!(Some(443), obj["services"]["web"]["port"].as_i64());
+        assert_eq!(2, options.import_cache.len());
+
+        std::fs::write(dir.join("shared/base.econ"), "{ region: \"changed\", replicas: 1 }").unwrap();
+        let again = Econ::from_path(dir.join("service.econ"), options.clone()).unwrap();
+        assert_eq!(Some("us-east"), again["base"]["region"].as_str());
+        options.import_cache.clear();
+        let fresh = Econ::from_path(dir.join("service.econ"), options).unwrap();
+        assert_eq!(Some("changed"), fresh["base"]["region"].as_str());
+
+        let err = Econ::from_path(dir.join("a.econ"), false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert!(err.message().starts_with("import: Import cycle:"), "{}", err.message());
+        assert!(err.message().ends_with("a.econ."), "{}", err.message());
+
+        let err = Econ::from_path(dir.join("x.econ"), false).unwrap_err();
+        assert_eq!(ErrorKind::Lex, err.kind());
+        assert!(err.message().starts_with("Include cycle:"), "{}", err.message());
+
+        let err = Econ::from_path(dir.join("service.econ"), EconOptions::new().includes(false)).unwrap_err();
+        assert_eq!("Includes are disabled.", err.message());
+        let err = Econ::create(r#"{ a: import("missing.econ") }"#, false).unwrap_err();
+        assert!(err.message().starts_with("import: Cannot read 'missing.econ'"), "{}", err.message());
+        assert!(Econ::create("{ import: 1 }", false).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolvers() {
+        use import::{EmbeddedResolver, ImportCache, MemoryResolver};
+        use options::EconOptions;
+
+        let files = MemoryResolver::new()
+            .file("app/main.econ", r#"{ @include("./macros.econ") db: import("../shared/db.econ"), @port(api, 80) }"#)
+            .file("app/macros.econ", "@port(name, n) name: n + 1\n")
+            .file("shared/db.econ", r#"{ host: "db", base: import("base.econ") }"#)
+            .file("shared/base.econ", "{ retries: 3 }");
+        let cache = ImportCache::new();
+        let options = EconOptions::new().resolver(files.clone()).import_cache(cache.clone());
+
+        let obj = Econ::from_path("app/main.econ", options.clone()).unwrap();
+        assert_eq!(Some("db"), obj["db"]["host"].as_str());
+        assert_eq!(Some(3f64), obj["db"]["base"]["retries"].as_f64());
+        assert_eq!(Some(81f64), obj["api"].as_f64());
+        assert_eq!(2, cache.len());
+        assert_eq!(obj, Econ::create("app/main.econ", options.clone()).unwrap());
+        let (recovered, errors) = Econ::create_recovering("app/main.econ", options.clone());
+        assert!(errors.is_empty());
+        assert_eq!(obj, recovered);
+
+        // the cache is shared but a file is evaluated again under other variables
+        let files = files.file("app/env.econ", r#"{ env: import("../shared/ctx.econ") }"#).file("shared/ctx.econ", "{ name: !stage }");
+        let shared = EconOptions::new().resolver(files.clone()).import_cache(cache.clone());
+        let prod = Econ::from_path("app/env.econ", shared.clone().with_var("stage", "prod")).unwrap();
+        let dev = Econ::from_path("app/env.econ", shared.clone().with_var("stage", "dev")).unwrap();
+        let prod_again = Econ::from_path("app/env.econ", shared.with_var("stage", "prod")).unwrap();
+        assert_eq!((Some("prod"), Some("dev")), (prod["env"]["name"].as_str(), dev["env"]["name"].as_str()));
+        assert_eq!(prod, prod_again);
+        assert_eq!(4, cache.len());
+
+        let err = Econ::from_path("app/other.econ", options.clone()).unwrap_err();
+        assert_eq!(error::ErrorKind::Io, err.kind());
+        assert_eq!(Some("app/other.econ"), err.file());
+        let err = Econ::from_str(r#"{ a: import("nope.econ") }"#, options).unwrap_err();
+        assert!(err.message().starts_with("import: Cannot read 'nope.econ'"), "{}", err.message());
+
+        let cyclic = files
+            .file("a.econ", r#"{ b: import("b.econ") }"#)
+            .file("b.econ", r#"{ a: import("./a.econ") }"#);
+        let err = Econ::from_path("a.econ", EconOptions::new().resolver(cyclic)).unwrap_err();
+        assert_eq!("import: Import cycle: a.econ -> b.econ -> a.econ.", err.message());
+
+        static EMBEDDED: EmbeddedResolver = EmbeddedResolver::new(&[("main.econ", r#"{ x: import("x.econ") }"#), ("x.econ", "{ y: true }")]);
+        let obj = Econ::from_path("main.econ", EconOptions::new().resolver(EMBEDDED)).unwrap();
+        assert_eq!(Some(true), obj["x"]["y"].as_bool());
+
+        struct Counting(MemoryResolver, std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+        impl import::SourceResolver for Counting {
+            fn resolve(&self, from: &std::path::Path, spec: &str) -> Result<(import::CanonicalId, String), error::EconError> {
+                self.1.lock().unwrap().push(spec.to_string());
+                self.0.resolve(from, spec)
+            }
+        }
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let files = MemoryResolver::new()
+            .file("root.econ", r#"{ @include("m.econ") a: import("x.econ"), b: import("y.econ"), @m() }"#)
+            .file("m.econ", "@m() c: true\n")
+            .file("x.econ", "{ x: 1 }")
+            .file("y.econ", "{ y: 2 }");
+        let obj = Econ::from_path("root.econ", EconOptions::new().resolver(Counting(files, seen.clone()))).unwrap();
+        assert_eq!(Some(true), obj["c"].as_bool());
+        assert_eq!(vec!("root.econ", "m.econ", "x.econ", "y.econ"), *seen.lock().unwrap());
+    }
+
+    #[test]
+    fn bindings() {
+        use options::EconOptions;
+
+        let obj = Econ::create(
+            r#"
+        {
+            let rate = 1.5,
+            let double = x => $x * 2,
+            fn fact(n) => $n < 2 ? 1 : $n * $fact($n - 1),
+            fn price(n) => $n * $rate,
+            a: $double(21),
+            b: $fact(5),
+            c: map([1, 2, 3], $double),
+            d: {
+                rate: 10,
+                e: !price(2),
+                f: !fact(3) + #[$rate]
+            },
+            g: fold([1, 2, 3], |x, acc| => $acc + !double($x))
+        }
+        "#,
+            false,
+        ).unwrap();
+
+        assert_eq!(vec!["a", "b", "c", "d", "g"], obj.keys().collect::<Vec<_>>());
+        assert_eq!(Some(42f64), obj["a"].as_f64());
+        assert_eq!(Some(120f64), obj["b"].as_f64());
+        assert_eq!(Econ::from("[2, 4, 6]"), obj["c"]);
+        assert_eq!(Some(3f64), obj["d"]["e"].as_f64());
+        assert_eq!(Some(7f64), obj["d"]["f"].as_f64());
+        assert_eq!(Some(12f64), obj["g"].as_f64());
+
+        // keys forced during a call don't see its references
+        let obj = Econ::create("{ fn f(x) => $y, a: $f(1), x: 100, y: $x }", false).unwrap();
+        assert_eq!((Some(100f64), Some(100f64)), (obj["a"].as_f64(), obj["y"].as_f64()));
+        let obj = Econ::create("{ fn f(x) => $y + $x, a: $f(1), y: $x * 2, x: 100 }", false).unwrap();
+        assert_eq!((Some(201f64), Some(200f64)), (obj["a"].as_f64(), obj["y"].as_f64()));
+        let obj = Econ::create("{ fn f() => $x, a: map([1], x => $f()), x: 5, b: map([1], x => { y: !x }) }", false).unwrap();
+        assert_eq!((Some(5f64), Some(1f64)), (obj["a"][0].as_f64(), obj["b"][0]["y"].as_f64()));
+
+        let err = Econ::create("{ fn loop(n) => $loop($n + 1), a: $loop(0) }", EconOptions::new().max_call_depth(16)).unwrap_err();
+        assert_eq!(ErrorKind::Type, err.kind());
+        assert_eq!("Calls nest deeper than the maximum of 16.", err.message());
+
+        let err = Econ::create("{ let double = x => $x * 2, a: $double }", false).unwrap_err();
+        assert_eq!("'$double' is a function, call it with '$double(...)'.", err.message());
+        let err = Econ::create("{ let double = x => $x * 2, a: $double(1, 2) }", false).unwrap_err();
+        assert_eq!("$double: Expected 1 arguments got 2.", err.message());
+        let err = Econ::create("{ a: 1, b: $a(1) }", false).unwrap_err();
+        assert_eq!(ErrorKind::Reference, err.kind());
+        assert!(Econ::create("{ let: 1, fn: 2 }", false).is_ok());
+        let obj = Econ::create("{ let double=x => $x * 2, let n=3, a=b: $double($n) }", false).unwrap();
+        assert_eq!(Some(6f64), obj["a=b"].as_f64());
+        let mut lexer = lexer::EconLexer::init(r#""let" a=b let c=d"#);
+        let tokens: Vec<lexer::Token> = (0..5).map(|_| lexer.scan().unwrap().token).collect();
+        let str = |s: &str| lexer::Token::Str(s.to_string());
+        assert_eq!(vec![str("let"), str("a=b"), str("let"), str("c"), lexer::Token::Assign], tokens);
+        let obj = Econ::create(r#"{ a: ["let"], b: $a[0] == "let" ? 1 : 2 }"#, false).unwrap();
+        assert_eq!(Some(1f64), obj["b"].as_f64());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimals() {
+        use rust_decimal::Decimal;
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Prices {
+            total: Decimal,
+            rate: Decimal,
+            cents: u32,
+            approx: f64,
+        }
+
+        let obj = Econ::from(
+            r#"
+        {
+            total: 0.1 + 0.2,
+            rate: ((1 + 3) / 2) * (5 / 3),
+            cents: 12.50 * 2,
+            approx: 0.1 + 0.2,
+            cheaper: 19.99 < 20,
+            neg: -7.5 % 2
+        }
+        "#,
+        );
+
+        let prices: Prices = Econ::to_struct(&obj).unwrap();
+        assert_eq!("0.3", prices.total.to_string());
+        assert_eq!("3.3333333333333333333333333334", prices.rate.to_string());
+        assert_eq!(25, prices.cents);
+        assert_eq!(0.3, prices.approx);
+        assert!(matches!(obj["cheaper"], EconValue::Bool(true)));
+        assert_eq!("0.5", obj["neg"].try_value::<Decimal>().unwrap().to_string());
+        assert_eq!(r#"{"a":0.3,"b":25}"#, Econ::from("{ a: 0.10 + 0.20, b: 12.50 * 2 }").to_canonical_json().unwrap());
+        assert_eq!(Econ::from("{ a: 1.50 }"), Econ::from("{ a: 1.5 }"));
+        assert_eq!(Econ::from("{ a: 2.0 }"), EconValue::Obj({ let mut o = EconObj::new(); o.insert("a", 2); o }));
+
+        assert_eq!(prices, from_value::<Prices>(&to_value(&prices).unwrap()).unwrap());
+        assert!(matches!(to_value(&obj["rate"]).unwrap(), EconValue::Dec(_)));
+        assert_eq!(obj, to_value(&obj).unwrap());
+
+        // numbers where a float carries them exactly, digits otherwise
+        assert_eq!("0.3", serde_json::to_string(&obj["total"]).unwrap());
+        assert_eq!("25", serde_json::to_string(&obj["cents"]).unwrap());
+        assert_eq!(r#""3.3333333333333333333333333334""#, serde_json::to_string(&obj["rate"]).unwrap());
+        let json = serde_json::Value::deserialize(&obj).unwrap();
+        assert_eq!(serde_json::json!(0.3), json["total"]);
+        assert_eq!(serde_json::json!(25), json["cents"]);
+        assert_eq!(serde_json::json!(0.5), json["neg"]);
+        assert_eq!(serde_json::json!("3.3333333333333333333333333334"), json["rate"]);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal_comparison() {
+        use std::{collections::hash_map::DefaultHasher, hash::{Hash, Hasher}, str::FromStr};
+        use rust_decimal::Decimal;
+
+        fn hash(v: &EconValue) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let int = EconValue::Int(9007199254740993);
+        let dec = EconValue::Dec(Decimal::from(9007199254740993i64));
+        let num = EconValue::Num(9007199254740992.0);
+        assert_eq!(int, dec);
+        assert_ne!(dec, num);
+        assert_ne!(int, num);
+        assert!(num < dec && num < int);
+
+        let equal = [
+            (EconValue::Dec(Decimal::from_str("0.5").unwrap()), EconValue::Num(0.5)),
+            (EconValue::Dec(Decimal::from_str("2.500").unwrap()), EconValue::Num(2.5)),
+            (EconValue::Dec(Decimal::from_str("-0.00").unwrap()), EconValue::Num(-0.0)),
+            (EconValue::Dec(Decimal::from(9007199254740992i64)), EconValue::Num(9007199254740992.0)),
+            (EconValue::Dec(Decimal::from_str("0.0000000000000000000000000001").unwrap()), EconValue::Dec(Decimal::from_str("1e-28").unwrap())),
+            (EconValue::Dec(Decimal::from(3)), EconValue::Int(3)),
+        ];
+        for (a, b) in &equal {
+            assert_eq!(a, b);
+            assert_eq!(hash(a), hash(b), "{:?} {:?}", a, b);
+        }
+
+        let point_one = EconValue::Dec(Decimal::from_str("0.1").unwrap());
+        assert_ne!(point_one, EconValue::Num(0.1));
+        assert!(point_one < EconValue::Num(0.1));
+        assert!(EconValue::Dec(Decimal::MAX) < EconValue::Num(1e30));
+        assert!(EconValue::Dec(Decimal::MIN) > EconValue::Num(f64::NEG_INFINITY));
+        assert!(EconValue::Dec(Decimal::from(-2)) < EconValue::Num(-1.5));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+/// Build an [`EconValue`](crate::value::EconValue) from Econ-like syntax.
+///
+/// Objects take `"quoted"` or bare keys and `(expr)` for a key computed in Rust. `nil` (or `null`), arrays and
+/// objects nest, and anything else is a Rust expression converted with `EconValue::from`, so variables,
+/// function calls and other `EconValue`s can be interpolated.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ, econ::Econ};
+///
+/// let port = 8080;
+/// let name = "api";
+/// let value = econ!({
+///     "a": 1,
+///     b: [true, nil, "x"],
+///     server: { (name): port + 1, tags: vec!["x", "y"] }
+/// });
+/// assert_eq!(Econ::from("{ a: 1, b: [true, nil, \"x\"], server: { api: 8081, tags: [\"x\", \"y\"] } }"), value);
+/// ```
+#[macro_export]
+macro_rules! econ {
+    // Array elements, munched one value at a time into `[$elems,*]`.
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        vec![$($elems),*]
+    };
+    (@array [$($elems:expr,)*] nil $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!(nil)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!(nil)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!({$($object)*})] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!($next),] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::econ!(@array [$($elems,)* $crate::econ!($last)])
+    };
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::econ!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // Object entries. The key is gathered in `($key)`, then the value is read from `($rest)`.
+    (@object $object:ident () () ()) => {};
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert($($key)+, $value);
+        $crate::econ!(@object $object () ($($rest)*) ($($rest)*));
+    };
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $object.insert($($key)+, $value);
+    };
+    (@object $object:ident ($($key:tt)+) (: nil $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!(nil)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!(nil)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!({$($map)*})) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!($value)) , $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::econ!(@object $object [$($key)+] ($crate::econ!($value)));
+    };
+    (@object $object:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object ($key) (: $($rest)*) (: $($rest)*));
+    };
+    (@object $object:ident () ($key:ident : $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object (stringify!($key)) (: $($rest)*) (: $($rest)*));
+    };
+    (@object $object:ident () ($key:literal : $($rest:tt)*) $copy:tt) => {
+        $crate::econ!(@object $object ($key) (: $($rest)*) (: $($rest)*));
+    };
+
+    (nil) => {
+        $crate::value::EconValue::Nil
+    };
+    (null) => {
+        $crate::value::EconValue::Nil
+    };
+    ([]) => {
+        $crate::value::EconValue::Arr(vec![])
+    };
+    ([ $($tt:tt)+ ]) => {
+        $crate::value::EconValue::Arr($crate::econ!(@array [] $($tt)+))
+    };
+    ({}) => {
+        $crate::value::EconValue::Obj($crate::object::EconObj::new())
+    };
+    ({ $($tt:tt)+ }) => {
+        $crate::value::EconValue::Obj({
+            let mut object = $crate::object::EconObj::new();
+            $crate::econ!(@object object () ($($tt)+) ($($tt)+));
+            object
+        })
+    };
+    ($other:expr) => {
+        $crate::value::EconValue::from($other)
+    };
+}
@@ -1,8 +1,8 @@
 use std::fmt;
 
-use indexmap::IndexMap;
+use indexmap::{map::Entry, IndexMap};
 
-use crate::value::EconValue;
+use crate::{json::{JsonOptions, JsonWriter}, value::EconValue};
 
 pub trait Access<T> {
     fn get(&self, i: T) -> &EconValue;
@@ -20,6 +20,12 @@ impl fmt::Display for EconObj {
     }
 }
 
+impl Default for EconObj {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EconObj {
     const NIL: EconValue = EconValue::Nil;
 
@@ -29,236 +35,65 @@ impl EconObj {
         }
     }
 
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.data.contains_key(key)
+    }
+
+    /// Set `key`, returning what it held before. A new key goes at the end, an existing one keeps its place.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<EconValue>) -> Option<EconValue> {
+        self.data.insert(key.into(), value.into())
+    }
+
+    /// Remove `key`, keeping the order of the keys after it.
+    pub fn remove(&mut self, key: &str) -> Option<EconValue> {
+        self.data.shift_remove(key)
+    }
+
+    /// Move the value out of `key`, leaving `nil` in its place. `nil` if there is no such key.
+    pub fn take(&mut self, key: &str) -> EconValue {
+        self.data.get_mut(key).map(std::mem::take).unwrap_or_default()
+    }
+
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_, String, EconValue> {
+        self.data.entry(key.into())
+    }
+
+    /// The value at `key`, set to the result of `f` first if there isn't one.
+    pub fn get_or_insert_with(&mut self, key: impl Into<String>, f: impl FnOnce() -> EconValue) -> &mut EconValue {
+        self.data.entry(key.into()).or_insert_with(f)
+    }
+
+    /// Keep only the keys for which `f` returns true, in their original order.
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &mut EconValue) -> bool) {
+        self.data.retain(|k, v| f(k, v));
+    }
+
     pub fn stringify(&self) -> String {
         format!("{}", self)
     }
 
-    pub fn get_string_from_arr(&self, arr: &Vec<EconValue>, depth: usize) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-    
-        for v in arr.iter() {
-            for _ in 0..depth+1 {
-                result.push_str("\t");
-            }
-            if let EconValue::Arr(a) = v {
-                result.push_str(&format!("[\n"));
-
-                if i+1 < arr.len() {
-                    result.push_str(&format!("{},\n", &self.get_string_from_arr(&a, depth+1)));
-                } else {
-                    result.push_str(&format!("{}\n", &self.get_string_from_arr(&a, depth+1)));
-                }            
-            } else {
-                match v {
-                    EconValue::Bool(b) => {
-                        if i+1 < arr.len() {
-                            result.push_str(&format!("{},\n", b));
-                        } else {
-                            result.push_str(&format!("{}\n", b));
-                        }
-                    }
-                    EconValue::Num(n) => {
-                        if i+1 < arr.len() {
-                            result.push_str(&format!("{},\n", n));
-                        } else {
-                            result.push_str(&format!("{}\n", n));
-                        }
-                    }
-                    EconValue::Str(s) => {
-                        if i+1 < arr.len() {
-                            for (ind, line) in s.lines().enumerate() {
-                                if ind == 0 {
-                                    if s.lines().count() == 1 {
-                                        result.push_str(&format!("\"{}", line));
-                                    } else {
-                                        result.push_str(&format!("\"{}\n", line));
-                                    }
-                                } else if ind == s.lines().count()-1 {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    result.push_str(&format!(" {}", line));
-                                } else {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    result.push_str(&format!(" {}\n", line));
-                                }
-                            }
-                            result.push_str(&format!("\",\n"));
-                        } else {
-                            for (ind, line) in s.lines().enumerate() {
-                                if ind == 0 {
-                                    if s.lines().count() == 1 {
-                                        result.push_str(&format!("\"{}", line));
-                                    } else {
-                                        result.push_str(&format!("\"{}\n", line));
-                                    }
-                                } else if ind == s.lines().count()-1 {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    result.push_str(&format!(" {}", line));
-                                } else {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    result.push_str(&format!(" {}\n", line));
-                                }
-                            }
-                            result.push_str(&format!("\"\n"));
-                        }
-                    }
-                    EconValue::Nil => {
-                        if i+1 < arr.len() {
-                            result.push_str(&format!("null,\n"));
-                        } else {
-                            result.push_str(&format!("null\n"));
-                        }
-                    }
-                    EconValue::Obj(o) => {
-                        if i+1 < arr.len() {
-                            result.push_str(&format!("{},\n", &self.get_string_from_obj(&o, depth+1)));
-                        } else {
-                            result.push_str(&format!("{}\n", &self.get_string_from_obj(&o, depth+1)));
-                        }
-                    }
-                    _ => {} 
-                }
-            }
-            i += 1;
-        }
-        
-        for _ in 0..depth {
-            result.push_str("\t");
-        }
-        result.push_str(&format!("]"));
-        
-        result
+    /// `arr` as tab indented Json, nested `depth` levels deep.
+    pub fn get_string_from_arr(&self, arr: &[EconValue], depth: usize) -> String {
+        let options = JsonOptions::display();
+        let mut writer = JsonWriter::new(&options);
+        let _ = writer.arr(arr, depth);
+        writer.out
     }
-        
-    pub fn get_string_from_obj(&self, obj: &EconObj, depth: usize) -> String {
-        let mut result = String::new();
-        let mut i = 0;
-        
-        result.push_str(&format!("{{\n"));
 
-        for (k, v) in obj.data.iter() {
-            for _ in 0..depth+1 {
-                result.push_str("\t");
-            }
-            if let EconValue::Obj(o) = v {
-                result.push_str(&format!("\"{}\": ", k));
-                
-                result.push_str(&self.get_string_from_obj(&o, depth+1));
-                
-                if i+1 < obj.data.keys().len() {
-                    result.push_str(",\n");
-                } else {
-                    result.push('\n');
-                }
-            } else {
-                match v {
-                    EconValue::Bool(b) => {
-                        if i+1 < obj.data.keys().len() {
-                            result.push_str(&format!("\"{}\": {},\n", k, b));
-                        } else {
-                            result.push_str(&format!("\"{}\": {}\n", k, b));
-                        }
-                    }
-                    EconValue::Num(n) => {
-                        if i+1 < obj.data.keys().len() {
-                            result.push_str(&format!("\"{}\": {},\n", k, n));
-                        } else {
-                            result.push_str(&format!("\"{}\": {}\n", k, n));
-                        }
-                    }
-                    EconValue::Str(s) => {
-                        if i+1 < obj.data.keys().len() {
-                            for (ind, line) in s.lines().enumerate() {
-                                if ind == 0 {
-                                    if s.lines().count() == 1 {
-                                        result.push_str(&format!("\"{}\": \"{}", k, line));
-                                    } else {
-                                        result.push_str(&format!("\"{}\": \"{}\n", k, line));
-                                    }
-                                } else if ind == s.lines().count()-1 {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    for _ in 0..5+k.len() {
-                                        result.push(' ');
-                                    }
-                                    result.push_str(&format!("{}", line));
-                                } else {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    for _ in 0..5+k.len() {
-                                        result.push(' ');
-                                    }
-                                    result.push_str(&format!("{}\n", line));
-                                }
-                            }
-                            result.push_str(&format!("\",\n"));
-                        } else {
-                            for (ind, line) in s.lines().enumerate() {
-                                if ind == 0 {
-                                    if s.lines().count() == 1 {
-                                        result.push_str(&format!("\"{}\": \"{}", k, line));
-                                    } else {
-                                        result.push_str(&format!("\"{}\": \"{}\n", k, line));
-                                    }
-                                } else if ind == s.lines().count()-1 {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    for _ in 0..5+k.len() {
-                                        result.push(' ');
-                                    }
-                                    result.push_str(&format!("{}", line));
-                                } else {
-                                    for _ in 0..depth+1 {
-                                        result.push_str("\t");
-                                    }
-                                    for _ in 0..5+k.len() {
-                                        result.push(' ');
-                                    }
-                                    result.push_str(&format!("{}\n", line));
-                                }
-                            }
-                            result.push_str(&format!("\"\n"));
-                        }
-                    }
-                    EconValue::Nil => {
-                        if i+1 < obj.data.keys().len() {
-                            result.push_str(&format!("\"{}\": null,\n", k));
-                        } else {
-                            result.push_str(&format!("\"{}\": null\n", k));
-                        }
-                    }
-                    EconValue::Arr(a) => {
-                        result.push_str(&format!("\"{}\": [\n", k));
-                        result.push_str(&self.get_string_from_arr(&a, depth+1));
-                        if i+1 < obj.data.keys().len() {
-                            result.push_str(",\n");
-                        } else {
-                            result.push('\n');
-                        }
-                    }
-                    _ => {} 
-                }
-            }
-            
-            i += 1;
-        }
-        for _ in 0..depth {
-            result.push_str("\t");
-        }
-        result.push_str(&format!("}}"));
-        
-        result
+    /// `obj` as tab indented Json, nested `depth` levels deep.
+    pub fn get_string_from_obj(&self, obj: &EconObj, depth: usize) -> String {
+        let options = JsonOptions::display();
+        let mut writer = JsonWriter::new(&options);
+        let _ = writer.obj(obj, depth);
+        writer.out
     }
 }
 
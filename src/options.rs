@@ -0,0 +1,313 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{error::EconError, import::{FsResolver, ImportCache, SourceResolver}, value::EconValue};
+
+/// Receives trace output line by line.
+pub type TraceSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// A function implemented in Rust and called from Econ source, see [`EconOptions::register_fn`].
+pub type NativeFn = Arc<dyn Fn(&[EconValue]) -> Result<EconValue, EconError> + Send + Sync>;
+
+/// What gets written to the trace sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trace {
+    /// The source being parsed
+    Source,
+    /// Every token produced by the lexer, macros expanded
+    Tokens,
+    /// Time taken by each stage
+    Timings,
+    /// The expression tree
+    Ast,
+    /// The resulting value
+    Value,
+    /// Errors, rendered with the surrounding source
+    Errors,
+}
+
+impl Trace {
+    pub const ALL: [Trace; 6] = [Trace::Source, Trace::Tokens, Trace::Timings, Trace::Ast, Trace::Value, Trace::Errors];
+}
+
+/// Which environment variables `env`, `env_num` and `env_bool` may read.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EnvAccess {
+    All,
+    /// Every read is a reference error
+    Deny,
+    /// Only the listed names, any other is a reference error
+    Allow(Vec<String>),
+}
+
+impl EnvAccess {
+    pub fn allows(&self, name: &str) -> bool {
+        match self {
+            EnvAccess::All => true,
+            EnvAccess::Deny => false,
+            EnvAccess::Allow(names) => names.iter().any(|n| n == name),
+        }
+    }
+}
+
+/// Configures lexing, parsing and evaluation. Passed to [`Econ::create`](crate::econ::Econ::create) and friends, or to
+/// [`EconLexer`](crate::lexer::EconLexer), [`EconParser`](crate::parser::EconParser) and
+/// [`EconEvaluator`](crate::evaluator::EconEvaluator) directly.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, options::{EconOptions, Trace}};
+///
+/// let options = EconOptions::new()
+///     .strict_refs(true)
+///     .max_depth(32)
+///     .macros(false)
+///     .trace_to(|line| eprintln!("{}", line))
+///     .trace(&[Trace::Errors]);
+///
+/// let obj = Econ::create("{ a: 1, b: $a + 1 }", options).unwrap();
+/// assert_eq!(2f64, obj["b"].value::<f64>());
+/// ```
+#[derive(Clone)]
+pub struct EconOptions {
+    pub strict_refs: bool,
+    pub allow_duplicate_keys: bool,
+    pub max_depth: Option<usize>,
+    pub max_source_len: Option<usize>,
+    /// How many calls to `let` and `fn` functions may be in progress at once, which bounds recursion.
+    pub max_call_depth: usize,
+    pub macros: bool,
+    pub functions: bool,
+    pub traces: Vec<Trace>,
+    pub sink: Option<TraceSink>,
+    pub source_name: Option<Arc<str>>,
+    /// Functions registered with [`register_fn`](EconOptions::register_fn) and their arity, by name.
+    pub native_fns: HashMap<String, (usize, NativeFn)>,
+    /// Values injected with [`with_var`](EconOptions::with_var), by name.
+    pub vars: HashMap<String, EconValue>,
+    pub env: EnvAccess,
+    pub includes: bool,
+    pub import_cache: ImportCache,
+    /// Where files are loaded from, disk unless [`resolver`](EconOptions::resolver) says otherwise.
+    pub resolver: Arc<dyn SourceResolver>,
+    /// Ids of the files being included, outermost first and ending with the current file if it has one.
+    pub(crate) including: Vec<String>,
+    /// Ids of the files being imported, outermost first and ending with the current file if it has one.
+    pub(crate) importing: Vec<String>,
+}
+
+impl Default for EconOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EconOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EconOptions")
+            .field("strict_refs", &self.strict_refs)
+            .field("allow_duplicate_keys", &self.allow_duplicate_keys)
+            .field("max_depth", &self.max_depth)
+            .field("max_source_len", &self.max_source_len)
+            .field("max_call_depth", &self.max_call_depth)
+            .field("macros", &self.macros)
+            .field("functions", &self.functions)
+            .field("traces", &self.traces)
+            .field("sink", &self.sink.as_ref().map(|_| "Fn(&str)"))
+            .field("source_name", &self.source_name)
+            .field("native_fns", &self.native_fns.keys().collect::<Vec<_>>())
+            .field("vars", &self.vars)
+            .field("env", &self.env)
+            .field("includes", &self.includes)
+            .field("import_cache", &self.import_cache)
+            .field("resolver", &"dyn SourceResolver")
+            .finish()
+    }
+}
+
+/// `true` traces every stage to stdout, `false` is the default.
+impl From<bool> for EconOptions {
+    fn from(debug: bool) -> Self {
+        if debug {
+            Self::new().trace(&Trace::ALL).trace_to(|line| println!("{}", line))
+        } else {
+            Self::new()
+        }
+    }
+}
+
+impl EconOptions {
+    pub fn new() -> Self {
+        Self {
+            strict_refs: false,
+            allow_duplicate_keys: false,
+            max_depth: None,
+            max_source_len: None,
+            max_call_depth: 64,
+            macros: true,
+            functions: true,
+            traces: vec!(),
+            sink: None,
+            source_name: None,
+            native_fns: HashMap::new(),
+            vars: HashMap::new(),
+            env: EnvAccess::All,
+            includes: true,
+            import_cache: ImportCache::new(),
+            resolver: Arc::new(FsResolver),
+            including: vec!(),
+            importing: vec!(),
+        }
+    }
+
+    /// Make a reference to a key that doesn't exist an error instead of `nil`.
+    pub fn strict_refs(mut self, strict: bool) -> Self {
+        self.strict_refs = strict;
+        self
+    }
+
+    /// Let a repeated key replace the earlier one instead of failing with "Duplicate Key.".
+    pub fn allow_duplicate_keys(mut self, allow: bool) -> Self {
+        self.allow_duplicate_keys = allow;
+        self
+    }
+
+    /// Deepest nesting of objects, arrays, groups and function calls allowed. Also bounds how many keys may be
+    /// evaluated ahead of their turn at once to resolve forward references, 256 when unset.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Longest source, in bytes, allowed.
+    pub fn max_source_len(mut self, len: usize) -> Self {
+        self.max_source_len = Some(len);
+        self
+    }
+
+    /// Deepest nesting of calls to functions bound with `let` or `fn`, 64 by default.
+    pub fn max_call_depth(mut self, depth: usize) -> Self {
+        self.max_call_depth = depth;
+        self
+    }
+
+    /// Allow defining and invoking `@name(...)` macros.
+    pub fn macros(mut self, allow: bool) -> Self {
+        self.macros = allow;
+        self
+    }
+
+    /// Allow built in functions like `map` and `filter`.
+    pub fn functions(mut self, allow: bool) -> Self {
+        self.functions = allow;
+        self
+    }
+
+    /// Allow `env`, `env_num` and `env_bool` to read environment variables.
+    pub fn env(mut self, allow: bool) -> Self {
+        self.env = if allow { EnvAccess::All } else { EnvAccess::Deny };
+        self
+    }
+
+    /// Only let `env`, `env_num` and `env_bool` read the given variables.
+    pub fn env_allowlist<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.env = EnvAccess::Allow(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Allow `import("file")` and `@include("file")`.
+    pub fn includes(mut self, allow: bool) -> Self {
+        self.includes = allow;
+        self
+    }
+
+    /// Share evaluated imports with other options, see [`ImportCache`].
+    pub fn import_cache(mut self, cache: ImportCache) -> Self {
+        self.import_cache = cache;
+        self
+    }
+
+    /// Load files through `resolver` instead of from disk, see [`SourceResolver`].
+    pub fn resolver(mut self, resolver: impl SourceResolver + 'static) -> Self {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Write the given stages to the trace sink.
+    pub fn trace(mut self, traces: &[Trace]) -> Self {
+        self.traces = traces.to_vec();
+        self
+    }
+
+    /// Where trace output goes. Nothing is traced without a sink.
+    pub fn trace_to(mut self, sink: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Name shown in front of every error, usually the file the source was read from.
+    pub fn source_name(mut self, name: impl Into<Arc<str>>) -> Self {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Make `name(...)` callable from source. `f` gets exactly `arity` evaluated arguments, anything else is a
+    /// type error at the call. An error `f` returns is reported at the call too, with the function's name in
+    /// front of its message. Built in names like `map` can't be replaced.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, options::EconOptions, value::EconValue};
+    ///
+    /// let options = EconOptions::new().register_fn("port_for", 1, |args| {
+    ///     match args[0].try_value::<String>()?.as_str() {
+    ///         "api" => Ok(EconValue::from(8080)),
+    ///         _ => Ok(EconValue::Nil),
+    ///     }
+    /// });
+    ///
+    /// let obj = Econ::create("{ port: port_for(\"api\") + 1 }", options).unwrap();
+    /// assert_eq!(Some(8081), obj["port"].as_i64());
+    /// ```
+    pub fn register_fn(
+        mut self,
+        name: impl Into<String>,
+        arity: usize,
+        f: impl Fn(&[EconValue]) -> Result<EconValue, EconError> + Send + Sync + 'static,
+    ) -> Self {
+        self.native_fns.insert(name.into(), (arity, Arc::new(f)));
+        self
+    }
+
+    /// Make `value` readable from source as `!name`. Injected values sit outside the root object, so `!name`
+    /// finds them only when no enclosing object has a key called `name`, and `$` references never do.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ, econ::Econ, options::EconOptions};
+    ///
+    /// let options = EconOptions::new()
+    ///     .with_var("ctx", econ!({ env: "prod", region: "us-east" }))
+    ///     .with_var("version", 3);
+    ///
+    /// let obj = Econ::create("{ replicas: !ctx.env == \"prod\" ? 5 : 1, tag: to_string(!version) }", options).unwrap();
+    /// assert_eq!(Some(5), obj["replicas"].as_i64());
+    /// assert_eq!(Some("3"), obj["tag"].as_str());
+    /// ```
+    pub fn with_var(mut self, name: impl Into<String>, value: impl Into<EconValue>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// [`with_var`](EconOptions::with_var) for every entry of `vars`, e.g. an `EconObj` used as a context.
+    pub fn with_vars<K: Into<String>, V: Into<EconValue>>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self {
+        self.vars.extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    pub fn traces(&self, trace: Trace) -> bool {
+        self.sink.is_some() && self.traces.contains(&trace)
+    }
+
+    pub(crate) fn emit(&self, trace: Trace, line: impl FnOnce() -> String) {
+        if let (Some(sink), true) = (&self.sink, self.traces.contains(&trace)) {
+            sink(&line());
+        }
+    }
+}
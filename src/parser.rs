@@ -1,64 +1,73 @@
-use std::{collections::HashMap, time::Instant};
+use std::{sync::Arc, time::Instant};
 
-use crate::{lexer::{Function, EconLexer, Token, TokenData}, object::EconObj, value::EconValue};
+use crate::{
+    ast::{BinaryOp, Constraint, Entry, Expr, ExprKind, Item, Lambda, Object, UnaryOp, Var},
+    error::{Diagnostic, EconError, ErrorKind, Span},
+    evaluator::EconEvaluator,
+    lexer::{EconLexer, Function, Token, TokenData},
+    options::{EconOptions, Trace},
+    value::EconValue,
+};
 
-pub struct EconParser { 
+static EOF: Token = Token::EOF;
+
+pub struct EconParser {
     tokens: Vec<TokenData>,
     current: usize,
-    source: String,
-    locals: Vec<HashMap<String, EconValue>>,
-    constraints: Vec<HashMap<String, Vec<(usize, bool)>>>,
-    depth: isize,
-    in_constraint: bool
+    source: Arc<str>,
+    options: EconOptions,
+    depth: usize,
+    recover: bool,
+    errors: Vec<EconError>
 }
 
 impl EconParser {
     pub fn new(src: &str) -> Self {
+        Self::with_options(src, EconOptions::new())
+    }
+
+    pub fn with_options(src: &str, options: EconOptions) -> Self {
         Self {
             tokens: vec!(),
             current: 0,
-            source: String::from(src),
-            locals: vec!(),
-            constraints: vec!(),
-            depth: -1,
-            in_constraint: false
+            source: Arc::from(src),
+            options,
+            depth: 0,
+            recover: false,
+            errors: vec!()
         }
     }
-    
+
     fn peek(&self) -> &Token {
-        &self.tokens[self.current].token
+        self.tokens.get(self.current).map(|t| &t.token).unwrap_or(&EOF)
     }
-    
+
     fn peek_next(&self) -> &Token {
-        &self.tokens[self.current+1].token
-    }
-    
-    fn peek_full(&self) -> &TokenData {
-        &self.tokens[self.current]
+        self.tokens.get(self.current+1).map(|t| &t.token).unwrap_or(&EOF)
     }
-    
+
     fn advance(&mut self) -> &Token {
         self.current += 1;
         match self.tokens.get(self.current) {
-            Some(v) => { 
+            Some(v) => {
                 &v.token
             }
             None => { &self.tokens[self.current-1].token }
         }
     }
-    
+
     fn eat(&mut self) {
         self.current += 1;
     }
-    
+
     fn at_end(&self) -> bool {
         self.current >= self.tokens.len()
     }
-    
+
     fn check(&self, t: Token) -> bool {
-        !self.at_end() && *self.peek() == t 
+        !self.at_end() && *self.peek() == t
     }
-    
+
     fn match_single(&mut self, t1: Token) -> bool {
         if self.check(t1) {
             self.eat();
@@ -67,1911 +76,635 @@ impl EconParser {
             false
         }
     }
-    
-    fn error<T>(&self, msg: String) -> Result<T, String> {
-        let mut result_err = String::from("");
-        
-        result_err.push_str(&format!("Line [{:04}] Error Parsing -> \"{}\"\n", self.peek_full().line, msg.clone()));
-        
-        let data = self.peek_full().clone();
-        let current_line = data.line;
-
-        for (line_num, line) in self.source.lines().enumerate() {
-            if line_num+1 == current_line || (line_num != 0 && line_num-1 == current_line) {
-                result_err.push_str(&format!("[{:04}]   {}\n", line_num, line));
-            }
-            if line_num == current_line {
-                result_err.push_str(&format!("-> [{:04}]{}\n", line_num, line));
+
+    fn error<T>(&self, msg: String) -> Result<T, EconError> {
+        self.fail(ErrorKind::Parse, msg)
+    }
+
+    fn fail<T>(&self, kind: ErrorKind, msg: String) -> Result<T, EconError> {
+        let data = self.tokens.get(self.current).or(self.tokens.last());
+        let mut diagnostic = Diagnostic::new(msg, Default::default())
+            .with_source(self.source.clone())
+            .in_file(self.options.source_name.clone());
+
+        if let Some(data) = data {
+            diagnostic.span = data.span;
+            diagnostic = diagnostic.with_token(data.token.clone());
+        }
+
+        Err(EconError::from_diagnostic(kind, diagnostic))
+    }
+
+    /// Span from the token at `start` up to the last consumed token.
+    fn span_from(&self, start: usize) -> Span {
+        let end = self.current.saturating_sub(1).max(start);
+
+        match (self.tokens.get(start), self.tokens.get(end).or(self.tokens.last())) {
+            (Some(first), Some(last)) if last.span.end >= first.span.start => {
+                Span { end: last.span.end, ..first.span }
             }
+            (Some(first), _) => first.span,
+            _ => self.tokens.last().map(|t| t.span).unwrap_or_default(),
         }
-        
-        Err(result_err)
     }
 
-    // fn error_at<T>(&self, msg: String, current_line: usize) -> Result<T, String> {
-    //     let mut result_err = String::from("");
-        
-    //     result_err.push_str(&format!("Line [{:04}] Error Parsing -> \"{}\"\n", current_line, msg.clone()));
-
-    //     for (line_num, line) in self.source.lines().enumerate() {
-    //         if line_num+1 == current_line || (line_num != 0 && line_num-1 == current_line) {
-    //             result_err.push_str(&format!("[{:04}]   {}\n", line_num, line));
-    //         }
-    //         if line_num == current_line {
-    //             result_err.push_str(&format!("-> [{:04}]{}\n", line_num, line));
-    //         }
-    //     }
-        
-    //     Err(result_err)
-    // }
-    
-    fn consume(&mut self, t: Token, msg: String) -> Result<&Token, String>  {
-        if self.check(t) { 
+    /// Span of the next token, used for expressions that consume nothing.
+    fn span_here(&self) -> Span {
+        self.tokens.get(self.current).or(self.tokens.last()).map(|t| t.span).unwrap_or_default()
+    }
+
+    fn consume(&mut self, t: Token, msg: String) -> Result<&Token, EconError>  {
+        if self.check(t) {
             Ok(self.advance())
         } else {
             self.error(msg.clone())
         }
     }
-    
-    fn equality(&mut self) -> Result<EconValue, String> {
+
+    /// Run `f` one level of nesting deeper, failing if that goes past `max_depth`.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, EconError>) -> Result<T, EconError> {
+        if let Some(max) = self.options.max_depth {
+            if self.depth >= max {
+                return self.error(format!("Nesting exceeds the maximum depth of {}.", max));
+            }
+        }
+
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn binary(&self, op: BinaryOp, left: Expr, right: Expr, start: usize) -> Expr {
+        Expr::new(ExprKind::Binary { op, left: Box::new(left), right: Box::new(right) }, self.span_from(start))
+    }
+
+    fn equality(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
         let mut left = self.comparison()?;
-        
+
         while !self.at_end() {
-            match self.peek() {
-                Token::Equal => {
-                    self.eat();
-                    let right = self.comparison()?;
-
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Bool(n1==n2)
-                        }
-                        (&EconValue::Bool(ref n1), &EconValue::Bool(ref n2)) => {
-                            EconValue::Bool(n1==n2)
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Str(ref n2)) => {
-                            EconValue::Bool(n1==n2)
-                        }
-                        _ => return self.error(format!("Invalid '==' of types: {} and {}", left, right))
-                    };
-                }
-                Token::NotEqual => {
-                    self.eat();
-                    let right = self.comparison()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Bool(n1!=n2)
-                        }
-                        (&EconValue::Bool(ref n1), &EconValue::Bool(ref n2)) => {
-                            EconValue::Bool(n1!=n2)
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Str(ref n2)) => {
-                            EconValue::Bool(n1!=n2)
-                        }
-                        _ => return self.error(format!("Invalid '~=' of types: {} and {}", left, right))
-                    };
-                }
+            let op = match self.peek() {
+                Token::Equal => BinaryOp::Equal,
+                Token::NotEqual => BinaryOp::NotEqual,
                 Token::Question => {
                     self.eat();
-                    let right1 = self.equality()?;
+                    let then = self.equality()?;
                     self.consume(Token::Colon, "Expect ':'.".to_string())?;
-                    let right2 = self.equality()?;
-                    
-                    left = match &left {
-                        &EconValue::Bool(true) => {
-                            right1
-                        }
-                        &EconValue::Bool(false) => {
-                            right2
-                        }
-                        _ => return self.error(format!("Invalid ternary expected bool got: {}", left))
-                    };
+                    let otherwise = self.equality()?;
+
+                    left = Expr::new(ExprKind::Ternary {
+                        condition: Box::new(left),
+                        then: Box::new(then),
+                        otherwise: Box::new(otherwise)
+                    }, self.span_from(start));
+                    continue;
                 }
                 _ => { break; }
-            }
+            };
+            self.eat();
+            let right = self.comparison()?;
+            left = self.binary(op, left, right, start);
         }
-        
+
         Ok(left)
     }
-    
-    fn comparison(&mut self) -> Result<EconValue, String> {
+
+    fn comparison(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
         let mut left = self.term()?;
-        
+
         while !self.at_end() {
-            match self.peek() {
-                Token::Less => {
-                    self.eat();
-                    let right = self.term()?;
-                    
-                    left = match (&left, &right) {
-                        (EconValue::Num(n1), EconValue::Num(n2)) => {
-                            EconValue::Bool(*n1 < *n2)
-                        }
-                        (EconValue::Str(n1), EconValue::Str(n2)) => {
-                            EconValue::Bool(n1.to_lowercase().cmp(&n2.to_lowercase()).is_lt())
-                        }
-                        _ => return self.error(format!("Invalid '<' of types: {} and {}", left, right))
-                    };
-                }
-                Token::Greater => {
-                    self.eat();
-                    let right = self.term()?;
-                    left = match (&left, &right) {
-                        (EconValue::Num(n1), EconValue::Num(n2)) => {
-                            EconValue::Bool(*n1 > *n2)
-                        }
-                        (EconValue::Str(n1), EconValue::Str(n2)) => {
-                            EconValue::Bool(n1.to_lowercase().cmp(&n2.to_lowercase()).is_gt())
-                        }
-                        _ => return self.error(format!("Invalid '>' of types: {} and {}", left, right))
-                    };
-                }
-                Token::GreaterEqual => {
-                    self.eat();
-                    let right = self.term()?;
-                    left = match (&left, &right) {
-                        (EconValue::Num(n1), EconValue::Num(n2)) => {
-                            EconValue::Bool(*n1 >= *n2)
-                        }
-                        (EconValue::Str(n1), EconValue::Str(n2)) => {
-                            EconValue::Bool(n1.to_lowercase().cmp(&n2.to_lowercase()).is_ge())
-                        }
-                        _ => return self.error(format!("Invalid '>=' of types: {} and {}", left, right))
-                    };
-                }
-                Token::LessEqual => {
-                    self.eat();
-                    let right = self.term()?;
-                    left = match (&left, &right) {
-                        (EconValue::Num(n1), EconValue::Num(n2)) => {
-                            EconValue::Bool(*n1 <= *n2)
-                        }
-                        (EconValue::Str(n1), EconValue::Str(n2)) => {
-                            EconValue::Bool(n1.to_lowercase().cmp(&n2.to_lowercase()).is_le())
-                        }
-                        _ => return self.error(format!("Invalid '<=' of types: {} and {}", left, right))
-                    };
-                }
-                Token::And => {
-                    self.eat();
-                    let right = self.term()?;
-                    if let (EconValue::Bool(n1), EconValue::Bool(n2)) = (&left, right) {
-                        left = EconValue::Bool(*n1 && n2);
-                    } 
-                }
-                Token::Or => {
-                    self.eat();
-                    let right = self.term()?;
-                    if let (EconValue::Bool(n1), EconValue::Bool(n2)) = (&left, right) {
-                        left = EconValue::Bool(*n1 || n2);
-                    } 
-                }
+            let op = match self.peek() {
+                Token::Less => BinaryOp::Less,
+                Token::Greater => BinaryOp::Greater,
+                Token::GreaterEqual => BinaryOp::GreaterEqual,
+                Token::LessEqual => BinaryOp::LessEqual,
+                Token::And => BinaryOp::And,
+                Token::Or => BinaryOp::Or,
                 _ => { break; }
-            }
+            };
+            self.eat();
+            let right = self.term()?;
+            left = self.binary(op, left, right, start);
         }
-        
+
         Ok(left)
     }
-    
-    fn term(&mut self) -> Result<EconValue, String> {
+
+    fn term(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
         let mut left = self.factor()?;
-        
+
         while !self.at_end() {
-            match self.peek() {
-                Token::Plus => {
-                    self.eat();
-                    let right = self.factor()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Num(n1+n2)
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}{}", n1, n2))
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Str(format!("{}{}", n1, n2))
-                        }
-                        (&EconValue::Num(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}{}", n1, n2))
-                        }
-                        (&EconValue::Num(ref n1), &EconValue::Nil) =>  {
-                            EconValue::Num(*n1)
-                        }
-                        (&EconValue::Nil, &EconValue::Num(ref n1)) =>  {
-                            EconValue::Num(*n1)
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Nil) =>  {
-                            EconValue::Str(format!("{}", n1))
-                        }
-                        (&EconValue::Nil, &EconValue::Str(ref n1)) =>  {
-                            EconValue::Str(format!("{}", n1))
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Bool(ref n2)) =>  {
-                            EconValue::Str(format!("{}{}", n1, n2))
-                        }
-                        (&EconValue::Bool(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}{}", n1, n2))
-                        }
-                        (&EconValue::Arr(ref n1), &EconValue::Arr(ref n2)) =>  {
-                            let mut new_arr = vec!();
-                            
-                            for a in n1 {
-                                new_arr.push(a.clone());
-                            }
-                            for a in n2 {
-                                new_arr.push(a.clone());
-                            }
-
-                            EconValue::Arr(new_arr)
-                        }
-                        (&EconValue::Arr(ref n1), &EconValue::Nil) =>  {
-                            let mut new_arr = vec!();
-                            
-                            for a in n1 {
-                                new_arr.push(a.clone());
-                            }
-
-                            EconValue::Arr(new_arr)
-                        }
-                        (&EconValue::Nil, &EconValue::Arr(ref n1)) =>  {
-                            let mut new_arr = vec!();
-                            
-                            for a in n1 {
-                                new_arr.push(a.clone());
-                            }
-
-                            EconValue::Arr(new_arr)
-                        }
-                        (&EconValue::Obj(ref n1), &EconValue::Obj(ref n2)) =>  {
-                            let mut new_obj = EconObj::new();
-                            
-                            for (k, v) in &n1.data {
-                                new_obj.data.insert(k.clone(), v.clone());
-                            }
-                            for (k, v) in &n2.data {
-                                new_obj.data.insert(k.clone(), v.clone());
-                            }
-
-                            EconValue::Obj(new_obj)
-                        }
-                        (&EconValue::Obj(ref n1), &EconValue::Nil) =>  {
-                            let mut new_obj = EconObj::new();
-                            
-                            for (k, v) in &n1.data {
-                                new_obj.data.insert(k.clone(), v.clone());
-                            }
-                            
-                            EconValue::Obj(new_obj)
-                        }
-                        (&EconValue::Nil, &EconValue::Obj(ref n1)) =>  {
-                            let mut new_obj = EconObj::new();
-                            
-                            for (k, v) in &n1.data {
-                                new_obj.data.insert(k.clone(), v.clone());
-                            }
-                            
-                            EconValue::Obj(new_obj)
-                        }
-                        _ => return self.error(format!("Invalid '+' of types: {} and {}", left, right))
-                    };
-                }
-                Token::BackSlash => {
-                    self.eat();
-                    let right = self.factor()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Str(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}\n{}", n1, n2))
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Str(format!("{}\n{}", n1, n2))
-                        }
-                        (&EconValue::Num(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}\n{}", n1, n2))
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Nil) =>  {
-                            EconValue::Str(format!("{}", n1))
-                        }
-                        (&EconValue::Nil, &EconValue::Str(ref n1)) =>  {
-                            EconValue::Str(format!("{}", n1))
-                        }
-                        (&EconValue::Str(ref n1), &EconValue::Bool(ref n2)) =>  {
-                            EconValue::Str(format!("{}\n{}", n1, n2))
-                        }
-                        (&EconValue::Bool(ref n1), &EconValue::Str(ref n2)) =>  {
-                            EconValue::Str(format!("{}\n{}", n1, n2))
-                        }
-                        _ => return self.error(format!("Invalid '\' of types: {} and {}", left, right))
-                    };
-                }
-                Token::Minus => {
-                    self.eat();
-                    let right = self.factor()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Num(n1-n2)
-                        }
-                        _ => return self.error(format!("Invalid '-' of types: {} and {}", left, right))
-                    };
-                }
-                _ => {
-                    break;
-                }
-            }
+            let op = match self.peek() {
+                Token::Plus => BinaryOp::Add,
+                Token::BackSlash => BinaryOp::Concat,
+                Token::Minus => BinaryOp::Sub,
+                _ => { break; }
+            };
+            self.eat();
+            let right = self.factor()?;
+            left = self.binary(op, left, right, start);
         }
-        
+
         Ok(left)
     }
-    
-    fn factor(&mut self) -> Result<EconValue, String> {
+
+    fn factor(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
         let mut left = self.unary()?;
-        
+
         while !self.at_end() {
-            match self.peek() {
-                Token::Mult => {
-                    self.eat();
-                    let right = self.unary()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Num(n1*n2)
-                        }
-                        _ => return self.error(format!("Invalid '*' of types: {} and {}", left, right))
-                    };
-                }
-                Token::Div => {
-                    self.eat();
-                    let right = self.unary()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Num(n1/n2)
-                        }
-                        _ => return self.error(format!("Invalid '/' of types: {} and {}", left, right))
-                    };
-                }
-                Token::Percent => {
-                    self.eat();
-                    let right = self.unary()?;
-                    
-                    left = match (&left, &right) {
-                        (&EconValue::Num(ref n1), &EconValue::Num(ref n2)) =>  {
-                            EconValue::Num(n1.rem_euclid(*n2))
-                        }
-                        _ => return self.error(format!("Invalid '%' of types: {} and {}", left, right))
-                    };
-                }
-                _ => {
-                    break;
-                }
-            }
+            let op = match self.peek() {
+                Token::Mult => BinaryOp::Mul,
+                Token::Div => BinaryOp::Div,
+                Token::Percent => BinaryOp::Rem,
+                _ => { break; }
+            };
+            self.eat();
+            let right = self.unary()?;
+            left = self.binary(op, left, right, start);
         }
-        
+
         Ok(left)
     }
-    
-    fn unary(&mut self) -> Result<EconValue, String> {
-        match self.peek().clone() {
-            Token::Minus => {
+
+    fn unary(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
+        let op = match self.peek() {
+            Token::Minus => UnaryOp::Neg,
+            Token::Not => UnaryOp::Not,
+            Token::Sharp => UnaryOp::Len,
+            _ => { return self.primary(); }
+        };
+        self.eat();
+        let right = self.unary()?;
+
+        Ok(Expr::new(ExprKind::Unary { op, expr: Box::new(right) }, self.span_from(start)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, EconError> {
+        let start = self.current;
+        let kind = match self.peek().clone() {
+            Token::Fn(func) => {
+                return self.nested(|parser| parser.call(func));
+            }
+            Token::Nil => {
                 self.eat();
-                let right = self.unary()?;
-                if let EconValue::Num(n1) = right {
-                    Ok(EconValue::Num(-n1))
-                } else {
-                    Ok(right)
-                }
+                ExprKind::Nil
             }
-            Token::Not => {
+            Token::Num(n) => {
                 self.eat();
-                let right = self.unary()?;
-                if let EconValue::Bool(b1) = right {
-                    Ok(EconValue::Bool(!b1))
-                } else {
-                    Ok(right)
-                }
+                ExprKind::Num(n)
             }
-            Token::Sharp => {
+            Token::Int(n) => {
                 self.eat();
-                let right = self.unary()?;
-                match &right {
-                    &EconValue::Str(ref n1) => {
-                        Ok(EconValue::Num(n1.chars().count() as f64))
-                    }
-                    &EconValue::Num(ref n1) => {
-                        Ok(EconValue::Num(*n1))
-                    }
-                    &EconValue::Arr(ref n1) => {
-                        Ok(EconValue::Num(n1.len() as f64))
-                    }
-                    &EconValue::Obj(ref n1) => {
-                        Ok(EconValue::Num(n1.data.keys().len() as f64))
-                    }
-                    _ => return self.error(format!("Invalid '#' of type: {}", right))
-                }
+                ExprKind::Int(n)
             }
-            _ => {
-                self.primary()
+            #[cfg(feature = "decimal")]
+            Token::Dec(n) => {
+                self.eat();
+                ExprKind::Dec(n)
             }
-        }
-    }
-    
-    fn create_temp_var(&mut self, fn_name: &str) -> Result<(EconValue, Option<EconValue>), String> {
-        let i_name = self.val_expression()?;
-        let cached_val;
-                
-        if let EconValue::Str(ref s) = &i_name {
-            cached_val = if let Some(v) = self.locals[self.depth as usize].get(s) {
-                Some(v.clone())
-            } else {
-                None
-            };
-            self.locals[self.depth as usize].insert(s.clone(), EconValue::Nil);
-        } else {
-            return self.error(format!("{}: Invalid reference got {}.", fn_name, i_name))
-        }
-        
-        Ok((i_name.clone(), cached_val.clone()))
-    }
-    
-    fn restore_temp_var(&mut self, var: (EconValue, Option<EconValue>)) {
-        if let (EconValue::Str(ref s), Some(cache)) = (&var.0, var.1) {
-            self.locals[self.depth as usize].insert(s.clone(), cache.clone());
-        }
-    }
-    
-    fn filter_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        match &right {
-            EconValue::Arr(a) => {
-                self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-                
-                let temp_1 = self.create_temp_var(name)?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference {}.", name, temp_1.0))?;
-                let mut new_vec = vec!();
-                for (j, aa) in a.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        self.locals[self.depth as usize].insert(s.clone(), aa.clone());
-                    } 
-                    let goto_point = self.current;
-                    let condition = self.val_expression()?;
-                    
-                    match condition {
-                        EconValue::Bool(true) => {
-                            new_vec.push(aa.clone());
-                        }
-                        EconValue::Bool(false) => { }
-                        _ => {
-                            return self.error(format!("{}: condition must be boolean got {}.", name, condition));
-                        }
-                    }
-                    if j < a.len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-    
-                self.restore_temp_var(temp_1);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(EconValue::Arr(new_vec))
+            Token::Bool(b) => {
+                self.eat();
+                ExprKind::Bool(b)
             }
-            EconValue::Obj(a) => {
-                self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-                
-                let temp_1 = self.create_temp_var(name)?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference {}.", name, temp_1.0))?;
-                let mut new_obj = EconObj::new();
-                for (j, aa) in a.data.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        let mut key_val = EconObj::new();
-                        key_val.data.insert("key".to_string(), EconValue::Str(aa.0.clone()));
-                        key_val.data.insert("val".to_string(), aa.1.clone());
-                        self.locals[self.depth as usize].insert(s.clone(), 
-                            EconValue::Obj(key_val)
-                        );
-                    } 
-                    let goto_point = self.current;
-                    let condition = self.val_expression()?;
-                    
-                    match condition {
-                        EconValue::Bool(true) => {
-                            new_obj.data.insert(aa.0.clone(), aa.1.clone());
-                        }
-                        EconValue::Bool(false) => { }
-                        _ => {
-                            return self.error(format!("{}: condition must be boolean got {}.", name, condition));
-                        }
-                    }
-                    if j < a.data.keys().len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-                
-                self.restore_temp_var(temp_1);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(EconValue::Obj(new_obj))
+            Token::Str(s) => {
+                self.eat();
+                ExprKind::Str(s)
             }
-            _ => {
-                self.error(format!("{}: Invalid argument expected Object/Array got {}.", name, right))
+            Token::LeftCurl => {
+                self.eat();
+                self.nested(Self::block)?
             }
-        }
-    }
-    
-    fn keys_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        if let EconValue::Obj(s) = &right {
-            self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-            let mut new_vec = vec!();
-            for i in s.data.keys() {
-                new_vec.push(EconValue::Str(i.to_string()));
+            Token::LeftBracket => {
+                self.eat();
+                self.nested(Self::array)?
             }
-            Ok(EconValue::Arr(new_vec))
-        } else {
-            return self.error(format!("{}: Invalid argument expected Object got {}.", name, right));
-        }
-    }
-    
-    fn values_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        if let EconValue::Obj(s) = &right {
-            self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-            let mut new_vec = vec!();
-            for i in s.data.values() {
-                new_vec.push(i.clone());
+            Token::Var((depth, name)) => {
+                self.eat();
+                let var = Var { depth, name };
+                let target = if self.check(Token::LeftParen) {
+                    self.nested(|parser| parser.apply(var, start))?
+                } else {
+                    Expr::new(ExprKind::Var(var), self.span_from(start))
+                };
+                return self.accessors(target, start);
             }
-            Ok(EconValue::Arr(new_vec))
-        } else {
-            return self.error(format!("{}: Invalid argument expected Object got {}.", name, right));
-        }
-    }
-    
-    fn chars_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        if let EconValue::Str(s) = &right {
-            self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-            let mut new_vec = vec!();
-            for i in s.chars() {
-                new_vec.push(EconValue::Str(i.to_string()));
+            Token::LeftParen => {
+                self.eat();
+                let r = self.nested(Self::val_expression)?;
+                self.consume(Token::RightParen, "Expect ')'.".to_string())?;
+                ExprKind::Group(Box::new(r))
             }
-            Ok(EconValue::Arr(new_vec))
-        } else {
-            return self.error(format!("{}: Invalid argument expected String got {}.", name, right));
+            _ => {
+                return Ok(Expr::new(ExprKind::Nil, self.span_here()));
+            }
+        };
+
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    /// `.key` and `[index]` selectors following a reference.
+    fn accessors(&mut self, mut target: Expr, start: usize) -> Result<Expr, EconError> {
+        loop {
+            let bracket = match self.peek() {
+                Token::Dot => false,
+                Token::LeftBracket => true,
+                _ => { break; }
+            };
+            self.eat();
+
+            let key = if bracket {
+                let key = self.val_expression()?;
+                self.consume(Token::RightBracket, "Expect ']' after Array Variable.".to_string())?;
+                key
+            } else {
+                self.primary()?
+            };
+
+            target = Expr::new(ExprKind::Access {
+                target: Box::new(target),
+                key: Box::new(key),
+                bracket
+            }, self.span_from(start));
         }
+
+        Ok(target)
     }
-    
-    fn string_impl(&mut self, name: &str) -> Result<EconValue, String> {
+
+    fn call(&mut self, func: Function) -> Result<Expr, EconError> {
+        let start = self.current;
+        let name = func.name().to_string();
+        let name = name.as_str();
         self.eat();
         self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        fn dig(current: &EconValue) -> String {
-            match current {
-                &EconValue::Arr(ref a)  => {
-                    let mut new_str = String::from("");
-                    for i in a {
-                        match i {
-                            EconValue::Bool(b) => {
-                                if *b {
-                                    new_str.push_str("true");
-                                } else {
-                                    new_str.push_str("false");
-                                }
-                            }
-                            EconValue::Num(n) => {
-                                new_str.push_str(format!("{}", n).as_str());
-                            }
-                            EconValue::Str(s) => {
-                                new_str.push_str(s);
-                            }
-                            EconValue::Nil => {
-                                new_str.push_str("nil");
-                            }
-                            _ => { new_str.push_str(dig(i).as_str()); }
-                        }
-                    }
-                    new_str
-                }
-                &EconValue::Obj(ref a)  => {
-                    let mut new_str = String::from("");
-                    for i in a.data.values() {
-                        match i {
-                            EconValue::Bool(b) => {
-                                if *b {
-                                    new_str.push_str("true");
-                                } else {
-                                    new_str.push_str("false");
-                                }
-                            }
-                            EconValue::Num(n) => {
-                                new_str.push_str(format!("{}", n).as_str());
-                            }
-                            EconValue::Str(s) => {
-                                new_str.push_str(s);
-                            }
-                            EconValue::Nil => {
-                                new_str.push_str("nil");
-                            }
-                            _ => { new_str.push_str(dig(i).as_str()); }
-                        }
-                    }
-                    new_str
-                }
-                EconValue::Bool(b) => {
-                    if *b {
-                        String::from("true")
-                    } else {
-                        String::from("false")
-                    }
-                }
-                EconValue::Num(n) => {
-                    format!("{}", n)
-                }
-                EconValue::Str(s) => {
-                    s.to_string()
-                }
-                EconValue::Nil => {
-                    String::from("nil")
-                }
+
+        let mut args = vec!();
+        while !self.check(Token::RightParen) && !self.at_end() {
+            args.push(self.argument(name)?);
+            if !self.check(Token::RightParen) {
+                self.consume(Token::Comma, format!("{}: Expect ',' after arg {}.", name, args.len()))?;
             }
         }
 
-        self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-        Ok(EconValue::Str(dig(&right)))
+        self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?;
+        Ok(Expr::new(ExprKind::Call { func, args }, self.span_from(start)))
     }
-    
-    fn map_impl(&mut self, name: &str) -> Result<EconValue, String> {
+
+    /// `(args)` after a reference, calling the function it names.
+    fn apply(&mut self, func: Var, start: usize) -> Result<Expr, EconError> {
+        let name = func.to_string();
         self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        
-        match right {
-            EconValue::Arr(a) => {
-                self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-                
-                let temp_1 = self.create_temp_var(name)?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference {}.", name, temp_1.0))?;
-                let mut new_vec = vec!();
-                for (j, aa) in a.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        self.locals[self.depth as usize].insert(s.clone(), aa.clone());
-                    } 
-                    let goto_point = self.current;
-                    let expr = self.val_expression()?;
-                    
-                    new_vec.push(expr);
-    
-                    if j < a.len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-                
-                self.restore_temp_var(temp_1);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(EconValue::Arr(new_vec))
-            }
-            EconValue::Obj(a) => {
-                self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-                
-                let temp_1 = self.create_temp_var(name)?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after iterator {}.", name, temp_1.0))?;
-                let mut new_obj = EconObj::new();
-                for (j, aa) in a.data.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        let mut key_val = EconObj::new();
-                        key_val.data.insert("key".to_string(), EconValue::Str(aa.0.clone()));
-                        key_val.data.insert("val".to_string(), aa.1.clone());
-                        self.locals[self.depth as usize].insert(s.clone(), 
-                            EconValue::Obj(key_val)
-                        );
-                    } 
-                    let goto_point = self.current;
-                    let expr = self.val_expression()?;
-                    
-                    new_obj.data.insert(aa.0.clone(), expr);
-    
-                    if j < a.data.keys().len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-                
-                self.restore_temp_var(temp_1);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(EconValue::Obj(new_obj))
-            }
-            _ => {
-                self.error(format!("{}: Invalid argument expected Object/Array got {}.", name, right))
+
+        let mut args = vec!();
+        while !self.check(Token::RightParen) && !self.at_end() {
+            args.push(self.val_expression()?);
+            if !self.check(Token::RightParen) {
+                self.consume(Token::Comma, format!("{}: Expect ',' after arg {}.", name, args.len()))?;
             }
         }
+
+        self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?;
+        Ok(Expr::new(ExprKind::Apply { func, args }, self.span_from(start)))
     }
-    
-    fn zip_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let a = self.val_expression()?;
-        self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-        let b = self.val_expression()?;
-        
-        let res = match (a, b) {
-            (EconValue::Arr(aa), EconValue::Arr(bb)) => {
-                let mut i = 0;
-                let mut ret = vec!();
-                loop {
-                    match (aa.get(i), bb.get(i)) {
-                        (Some(av), Some(bv)) => {
-                            ret.push(EconValue::Arr(vec![av.clone(), bv.clone()]))
-                        }
-                        (Some(av), None) => {
-                            ret.push(EconValue::Arr(vec![av.clone(), EconValue::Nil]))
-                        }
-                        (None, Some(bv)) => {
-                            ret.push(EconValue::Arr(vec![EconValue::Nil, bv.clone()]))
-                        }
-                        (None, None) => { break; }
-                    }
-                    i += 1;
+
+    /// A function argument: `x => body`, `|x, y| => body` or any value.
+    fn argument(&mut self, name: &str) -> Result<Expr, EconError> {
+        let start = self.current;
+        let params = match (self.peek().clone(), self.peek_next()) {
+            (Token::Pipe, _) => {
+                self.eat();
+                let mut params = vec!(self.reference(name)?);
+                while self.match_single(Token::Comma) {
+                    params.push(self.reference(name)?);
                 }
-                
-                ret
-            }
-            (EconValue::Arr(_), bb) => {
-                return self.error(format!("{}: Invalid argument 2 expected an Array got {}.", name, bb));
+                self.consume(Token::Pipe, format!("{}: Expect '|' after references.", name))?;
+                self.consume(Token::Arrow, format!("{}: Expect '=>' after '|'.", name))?;
+                params
             }
-            (aa, EconValue::Arr(_)) => {
-                return self.error(format!("{}: Invalid argument 1 expected an Array got {}.", name, aa));
+            (Token::Str(s), Token::Arrow) => {
+                self.eat();
+                self.eat();
+                vec!(s)
             }
-            (aa, bb) => {
-                return self.error(format!("{}: Invalid arguments expected Arrays got {} and {}.", name, aa, bb));
+            _ => {
+                return self.val_expression();
             }
         };
-        
-        self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-        Ok(EconValue::Arr(res))
-    }
-    
-    fn fold_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-        
-        match right {
-            EconValue::Arr(a) => {
-                self.consume(Token::Pipe, format!("{}: Expect '|' before references.", name))?;
-                let temp_1 = self.create_temp_var(name)?;
-                self.consume(Token::Comma, format!("{}: Expect ',' after reference 1.", name))?;
-                let temp_2 = self.create_temp_var(name)?;
-                self.consume(Token::Pipe, format!("{}: Expect '|' after references.", name))?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after '|'.", name))?;
 
-                for (j, aa) in a.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        self.locals[self.depth as usize].insert(s.clone(), 
-                            aa.clone()
-                        );
-                    }
-                    
-                    if let EconValue::Str(ref s) = &temp_2.0 {
-                        if let None = self.locals[self.depth as usize].get(s) {
-                            self.locals[self.depth as usize].insert(s.clone(), EconValue::Nil);
-                        }
-                    }
-                    
-                    let goto_point = self.current;
-                    let expr = self.val_expression()?;
-                    
-                    if let EconValue::Str(ref s) = &temp_2.0 {
-                        self.locals[self.depth as usize].insert(s.clone(), expr);
-                    }
+        let body = self.val_expression()?;
+        Ok(Expr::new(ExprKind::Lambda(Lambda { params, body: Box::new(body) }), self.span_from(start)))
+    }
 
-                    if j < a.len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-                
-                let ret_val = if let EconValue::Str(ref s) = &temp_2.0 {
-                    if let Some(rv) = self.locals[self.depth as usize].get(s) {
-                        rv.clone()
-                    } else {
-                        EconValue::Nil
-                    }
-                } else {
-                    EconValue::Nil
-                };
-                
-                self.restore_temp_var(temp_1);
-                self.restore_temp_var(temp_2);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(ret_val)
+    fn reference(&mut self, name: &str) -> Result<String, EconError> {
+        match self.peek().clone() {
+            Token::Str(s) => {
+                self.eat();
+                Ok(s)
             }
-            EconValue::Obj(a) => {
-                self.consume(Token::Pipe, format!("{}: Expect '|' before references.", name))?;
-                let temp_1 = self.create_temp_var(name)?;
-                self.consume(Token::Comma, format!("{}: Expect ',' after reference 1.", name))?;
-                let temp_2 = self.create_temp_var(name)?;
-                self.consume(Token::Pipe, format!("{}: Expect '|' after references.", name))?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after '|'.", name))?;
+            t => self.fail(ErrorKind::Reference, format!("{}: Invalid reference got {:?}.", name, t))
+        }
+    }
+
+    fn val_expression(&mut self) -> Result<Expr, EconError> {
+        self.equality()
+    }
 
-                for (j, aa) in a.data.iter().enumerate() {
-                    if let EconValue::Str(ref s) = &temp_1.0 {
-                        let mut key_val = EconObj::new();
+    fn array(&mut self) -> Result<ExprKind, EconError> {
+        let mut result = vec!();
 
-                        key_val.data.insert("key".to_string(), EconValue::Str(aa.0.clone()));
-                        key_val.data.insert("val".to_string(), aa.1.clone());
-                        self.locals[self.depth as usize].insert(s.clone(), 
-                            EconValue::Obj(key_val)
-                        );
-                    }
-                    
-                    if let EconValue::Str(ref s) = &temp_2.0 {
-                        if let None = self.locals[self.depth as usize].get(s) {
-                            self.locals[self.depth as usize].insert(s.clone(), EconValue::Nil);
-                        }
-                    }
-                    
-                    let goto_point = self.current;
-                    let expr = self.val_expression()?;
-                    
-                    if let EconValue::Str(ref s) = &temp_2.0 {
-                        self.locals[self.depth as usize].insert(s.clone(), expr);
-                    }
+        while !self.check(Token::RightBracket) && !self.at_end() {
+            let start = self.current;
 
-                    if j < a.data.keys().len()-1 {
-                        self.current = goto_point;
-                    }
-                }
-                
-                let ret_val = if let EconValue::Str(ref s) = &temp_2.0 {
-                    if let Some(rv) = self.locals[self.depth as usize].get(s) {
-                        rv.clone()
-                    } else {
-                        EconValue::Nil
-                    }
-                } else {
-                    EconValue::Nil
-                };
-                
-                self.restore_temp_var(temp_1);
-                self.restore_temp_var(temp_2);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(ret_val)
-            }
-            v => {
-                self.error(format!("{}: Invalid argument 1 expected an Array/Object got {}.", name, v))
+            if let Err(e) = self.array_item(&mut result) {
+                self.recover_from(e, start, Token::RightBracket)?;
             }
         }
-    }
-    
-    fn partition(&mut self, name: &str, a: &mut [EconValue], temp_1: &(EconValue, Option<EconValue>), temp_2: &(EconValue, Option<EconValue>)) -> Result<usize, String> {
-        let mut i = 0;
-        let right = a.len() - 1;
-     
-        for j in 0..right {
-            if let EconValue::Str(ref s) = &temp_1.0 {
-                self.locals[self.depth as usize].insert(s.clone(), 
-                    a[j].clone()
-                );
-            }
-            
-            if let EconValue::Str(ref s) = &temp_2.0 {
-                self.locals[self.depth as usize].insert(s.clone(), 
-                    a[right].clone()
-                );
-            }
-            
-            let goto_point = self.current;
-            let condition = self.val_expression()?;
-            
-            match condition {
-                EconValue::Bool(b) => {
-                    if b {
-                        a.swap(j, i);
-                        i += 1;
-                    }
-                }
-                v => {
-                    return self.error(format!("{}: condition must be boolean got {:?}.", name, v));
-                }
-            }
-            
-            self.current = goto_point;
+
+        if let Err(e) = self.consume(Token::RightBracket, "Expect ']' after array.".to_string()) {
+            self.recover_from(e, self.current, Token::RightBracket)?;
+            self.match_single(Token::RightBracket);
         }
-     
-        a.swap(i, right);
-        Ok(i)
+        Ok(ExprKind::Array(result))
     }
-     
-    fn quicksort(&mut self, name: &str, a: &mut [EconValue], temp_1: &(EconValue, Option<EconValue>), temp_2: &(EconValue, Option<EconValue>)) -> Result<(), String> {
-        if a.len() > 1 {
-            let q = self.partition(name, a, temp_1, temp_2)?;
-            self.quicksort(name, &mut a[..q], temp_1, temp_2)?;
-            self.quicksort(name, &mut a[q+1..], temp_1, temp_2)?;
-        }
 
+    fn array_item(&mut self, result: &mut Vec<Expr>) -> Result<(), EconError> {
+        let val = self.val_expression()?;
+        result.push(val);
+        if !self.check(Token::RightBracket) {
+            self.consume(Token::Comma, "Expect ',' or ']'.".to_string())?;
+        }
         Ok(())
     }
-    
-    fn sort_impl(&mut self, name: &str) -> Result<EconValue, String> {
-        self.eat();
-        self.consume(Token::LeftParen, format!("Expect '(' after {}.", name))?;
-        let right = self.val_expression()?;
-        self.consume(Token::Comma, format!("{}: Expect ',' after arg 1.", name))?;
-        
-        match right {
-            EconValue::Arr(a) => {
-                
-                
-                self.consume(Token::Pipe, format!("{}: Expect '|' before references.", name))?;
-                let temp_1 = self.create_temp_var(name)?;
-                self.consume(Token::Comma, format!("{}: Expect ',' after reference 1.", name))?;
-                let temp_2 = self.create_temp_var(name)?;
-                self.consume(Token::Pipe, format!("{}: Expect '|' after references.", name))?;
-                
-                self.consume(Token::Arrow, format!("{}: Expect '=>' after '|'.", name))?;
 
-                let mut new_vec = a.clone();
+    /// In recovering mode records `e` and skips ahead from the token at `start` to just past the next ','
+    /// or up to the `closer` that ends the enclosing object/array, ignoring anything nested in between.
+    /// Otherwise `e` is returned as is.
+    fn recover_from(&mut self, e: EconError, start: usize, closer: Token) -> Result<(), EconError> {
+        if !self.recover {
+            return Err(e);
+        }
 
-                self.quicksort(name, &mut new_vec[..], &temp_1, &temp_2)?;
-                self.val_expression()?;
-                
-                self.restore_temp_var(temp_1);
-                self.restore_temp_var(temp_2);
-                
-                self.consume(Token::RightParen, format!("Expect ')' after {} args.", name))?; 
-                Ok(EconValue::Arr(new_vec))
-            }
-            v => {
-                self.error(format!("{}: Invalid argument 1 expected an Array/Object got {}.", name, v))
-            }
+        // a failure at the same spot as the last one is a knock-on effect of it
+        if e.span().is_none() || self.errors.last().and_then(|last| last.span()) != e.span() {
+            self.errors.push(e);
         }
-    }
-    
-    fn primary(&mut self) -> Result<EconValue, String> {
-        match self.peek().clone() {
-            Token::Fn(func) => {
-                match func {
-                    Function::Filter => {
-                        self.filter_impl("filter")
-                    }
-                    Function::Map => {
-                        self.map_impl("map")
-                    }
-                    Function::Chars => {
-                        self.chars_impl("chars")
-                    }
-                    Function::ToString => {
-                        self.string_impl("to_string")
-                    }
-                    Function::Keys => {
-                        self.keys_impl("keys")
-                    }
-                    Function::Values => {
-                        self.values_impl("values")
-                    }
-                    Function::Fold => {
-                        self.fold_impl("fold")
-                    }
-                    Function::Zip => {
-                        self.zip_impl("zip")
-                    }
-                    Function::Sort => {
-                        self.sort_impl("sort")
-                    }
+
+        let mut nesting = 0usize;
+        let mut i = start;
+
+        while let Some(t) = self.tokens.get(i) {
+            match &t.token {
+                Token::LeftCurl | Token::LeftBracket | Token::LeftParen => nesting += 1,
+                t if *t == closer && nesting == 0 => {
+                    self.current = i;
+                    return Ok(());
                 }
-            }
-            Token::Nil => {
-                self.eat();
-                Ok(EconValue::Nil)
-            }
-            Token::Num(n) => {
-                self.eat();
-                Ok(EconValue::Num(n))
-            }
-            Token::Bool(b) => {
-                self.eat();
-                Ok(EconValue::Bool(b))
-            }
-            Token::Str(s) => {
-                self.eat();
-                Ok(EconValue::Str(s))
-            }
-            Token::LeftCurl => {
-                self.eat();
-                self.locals.push(HashMap::new());
-                self.constraints.push(HashMap::new());
-                self.depth += 1;
-                let obj = self.block()?;
-                self.locals.pop();
-                self.constraints.pop();
-                self.depth -= 1;
-                Ok(obj)
-            }
-            Token::LeftBracket => {
-                self.eat();
-                let obj = self.array()?;
-                Ok(obj)
-            }
-            Token::Var(v) => {
-                self.eat();
-                
-                if self.depth-v.0 < 0 {
-                    Ok(EconValue::Nil)
-                } else {
-                    let value = if v.0 >= 0 {
-                        match self.locals[(self.depth-v.0) as usize].get(&v.1) {
-                            Some(EconValue::Num(n)) => {
-                                Ok(EconValue::Num(*n))
-                            }
-                            Some(EconValue::Str(s)) => {
-                                Ok(EconValue::Str(s.to_string()))
-                            }
-                            Some(EconValue::Bool(b)) => {
-                                Ok(EconValue::Bool(*b))
-                            }
-                            Some(EconValue::Obj(o)) => {
-                                Ok(EconValue::Obj(o.clone()))
-                            }
-                            Some(EconValue::Arr(a)) => {
-                                Ok(EconValue::Arr(a.clone()))
-                            }
-                            _ => {
-                                Ok(EconValue::Nil)
-                            }
-                        }
-                    } else {
-                        let mut res = None;
-                        for i in (0..=self.depth).rev() {
-                            res = match self.locals[i as usize].get(&v.1) {
-                                Some(EconValue::Num(n)) => {
-                                    Some(EconValue::Num(*n))
-                                }
-                                Some(EconValue::Str(s)) => {
-                                    Some(EconValue::Str(s.to_string()))
-                                }
-                                Some(EconValue::Bool(b)) => {
-                                    Some(EconValue::Bool(*b))
-                                }
-                                Some(EconValue::Obj(o)) => {
-                                    Some(EconValue::Obj(o.clone()))
-                                }
-                                Some(EconValue::Arr(a)) => {
-                                    Some(EconValue::Arr(a.clone()))
-                                }
-                                _ => {
-                                    None
-                                }
-                            };
-                            
-                            if res.is_some() {
-                                break;
-                            }
-                        }
-                        if let Some(r) = res {
-                            Ok(r)
-                        } else {
-                            Ok(EconValue::Nil)
-                        }
-                    };
-                    
-                    match value {
-                        Ok(EconValue::Obj(o)) => {
-                            let mut c = EconValue::Obj(o.clone());
-                        
-                            let mut call_type = self.peek().clone();
-
-                            loop {
-                                
-                                call_type = if let Token::LeftBracket | Token::Dot = call_type {
-                                    self.peek().clone()
-                                } else {
-                                    break;
-                                };
-
-                                match call_type {
-                                    Token::Dot => self.consume(Token::Dot, "Expect '.' after Object Variable.".to_string())?,
-                                    Token::LeftBracket => self.consume(Token::LeftBracket, "Expect '[' after Object Variable.".to_string())?,
-                                    _ => break
-                                };
-
-                                match self.peek().clone() {
-                                    Token::Num(_) | Token::Var(_) | Token::Str(_) | Token::LeftParen 
-                                    | Token::Bool(_) | Token::Not | Token::Sharp | Token::LeftBracket
-                                    | Token::LeftCurl | Token::Nil | Token::Fn(_) => {
-                                        let val = match call_type {
-                                            Token::LeftBracket => self.val_expression()?,
-                                            Token::Dot => self.primary()?,
-                                            _ => self.primary()?
-                                        };
-                                        
-                                        match val {
-                                            EconValue::Str(s) => {
-                                                match c.clone() {
-                                                    EconValue::Obj(oo) => {
-                                                        if let Some(v) = oo.data.get(&s) {
-                                                            c = v.clone();
-                                                        } else {
-                                                            c = EconValue::Nil;
-                                                        }
-                                                    }
-                                                    _ => {
-                                                        return self.error("Expect key after selecter.".to_string())
-                                                    }
-                                                }
-                                            }
-                                            EconValue::Num(n) => {
-                                                match c.clone() {
-                                                    EconValue::Arr(aa) => {
-                                                        if n < 0.0 {
-                                                            c = EconValue::Nil
-                                                        } else {
-                                                            if let Some(v) = aa.get(n as usize) {
-                                                                c = v.clone()
-                                                            } else {
-                                                                c = EconValue::Nil
-                                                            }
-                                                        }
-                                                    }
-                                                    EconValue::Str(ss) => {
-                                                        if n < 0.0 {
-                                                            c = EconValue::Nil
-                                                        } else {
-                                                            if let Some(v) = ss.chars().nth(n as usize) {
-                                                                c = EconValue::Str(v.to_string())
-                                                            } else {
-                                                                c = EconValue::Nil
-                                                            }
-                                                        }
-                                                    }
-                                                    _ => {
-                                                        return self.error("Expect key after selecter.".to_string())
-                                                    }
-                                                }
-                                            }
-                                            _ => { 
-                                                return self.error("Expect key after selecter.".to_string())
-                                            }
-                                        }
-                                        if let Token::LeftBracket = call_type {
-                                            self.consume(Token::RightBracket, "Expect ']' after Array Variable.".to_string())?;
-                                        }
-                                    }
-                                    _ => { }
-                                }
-                            }
-                            
-                            Ok(c)
-                        }
-                        Ok(EconValue::Arr(a)) => {
-                            let mut c = EconValue::Arr(a.clone());
-                            
-                            let mut call_type = self.peek().clone();
-                            
-                            loop {
-                                call_type = if let Token::LeftBracket | Token::Dot = call_type {
-                                    self.peek().clone()
-                                } else {
-                                    break;
-                                };
-                                
-                                match call_type {
-                                    Token::Dot => self.consume(Token::Dot, "Expect '.' after Object Variable.".to_string())?,
-                                    Token::LeftBracket => self.consume(Token::LeftBracket, "Expect '[' after Object Variable.".to_string())?,
-                                    _ => break
-                                };
-                                
-                                match self.peek().clone() {
-                                    Token::Num(_) | Token::Var(_) | Token::Str(_) | Token::LeftParen 
-                                    | Token::Bool(_) | Token::Not | Token::Sharp | Token::LeftBracket
-                                    | Token::LeftCurl | Token::Nil => {
-                                        let val = match call_type {
-                                            Token::LeftBracket => self.val_expression()?,
-                                            Token::Dot => self.primary()?,
-                                            _ => self.primary()?
-                                        };
-                                        
-                                        match val {
-                                            EconValue::Str(s) => {
-                                                match c.clone() {
-                                                    EconValue::Obj(oo) => {
-                                                        if let Some(v) = oo.data.get(&s) {
-                                                            c = v.clone();
-                                                        } else {
-                                                            c = EconValue::Nil;
-                                                        }
-                                                    }
-                                                    _ => {
-                                                        return self.error("Expect index after selecter.".to_string())
-                                                    }
-                                                }
-                                            }
-                                            EconValue::Num(n) => {
-                                                match c.clone() {
-                                                    EconValue::Arr(aa) => {
-                                                        if n < 0.0 {
-                                                            c = EconValue::Nil
-                                                        } else {
-                                                            if let Some(v) = aa.get(n as usize) {
-                                                                c = v.clone()
-                                                            } else {
-                                                                c = EconValue::Nil
-                                                            }
-                                                        }
-                                                    }
-                                                    EconValue::Str(ss) => {
-                                                        if n < 0.0 {
-                                                            c = EconValue::Nil
-                                                        } else {
-                                                            if let Some(v) = ss.chars().nth(n as usize) {
-                                                                c = EconValue::Str(v.to_string())
-                                                            } else {
-                                                                c = EconValue::Nil
-                                                            }
-                                                        }
-                                                    }
-                                                    _ => {
-                                                        return self.error("Expect index after selecter.".to_string())
-                                                    }
-                                                }
-                                            }
-                                            _ => {
-                                                return self.error("Expect index after selecter.".to_string())
-                                            }
-                                        }
-                                        
-                                        if let Token::LeftBracket = call_type {
-                                            self.consume(Token::RightBracket, "Expect ']' after Array Variable.".to_string())?;
-                                        }
-                                    }
-                                    _ => { }
-                                }
-                            }
-                            
-                            Ok(c)
-                        }
-                        Ok(EconValue::Str(s)) => {
-                            match self.peek() {
-                                Token::Dot => {
-                                    self.eat();
-                                    let expr = self.val_expression()?;
-                                    match expr {
-                                        EconValue::Num(n) => {
-                                            if n < 0.0 {
-                                                Ok(EconValue::Nil)
-                                            } else {
-                                                if let Some(v) = s.chars().nth(n as usize) {
-                                                    Ok(EconValue::Str(v.to_string()))
-                                                } else {
-                                                    Ok(EconValue::Nil)
-                                                }
-                                            }
-                                        }
-                                        _ => {
-                                            self.error("Expect index after '.'".to_string())
-                                        }
-                                    }
-                                }
-                                Token::LeftBracket => {
-                                    self.eat();
-                                    let expr = self.val_expression()?;
-                                    match expr {
-                                        EconValue::Num(n) => {  
-                                            self.consume(Token::RightBracket, "Expect ']' after String Variable.".to_string())?;
-                                            if n < 0.0 {
-                                                Ok(EconValue::Nil)
-                                            } else {
-                                                if let Some(v) = s.chars().nth(n as usize) {
-                                                    Ok(EconValue::Str(v.to_string()))
-                                                } else {
-                                                    Ok(EconValue::Nil)
-                                                }
-                                            }
-                                            
-                                        }
-                                        _ => {
-                                            self.error("Expect index after '['".to_string())
-                                        }
-                                    }
-                                }
-                                _ => {
-                                    Ok(EconValue::Str(s.clone()))
-                                }
-                            }
-                        }
-                        _ => {
-                            value
-                        }
-                    }
+                Token::RightCurl | Token::RightBracket | Token::RightParen if nesting > 0 => nesting -= 1,
+                Token::Comma if nesting == 0 => {
+                    self.current = i + 1;
+                    return Ok(());
                 }
+                _ => {}
             }
-            Token::LeftParen => {
-                self.eat();
-                let r = self.val_expression();
-                self.consume(Token::RightParen, "Expect ')'.".to_string())?;
-                r
-            }
-            _ => { 
-                Ok(EconValue::Nil) 
-            }
-        }    
+            i += 1;
+        }
+
+        self.current = self.tokens.len();
+        Ok(())
     }
-    
-    fn val_expression(&mut self) -> Result<EconValue, String> {
-        let val = if let Token::Comma | Token::RightCurl | Token::RightBracket | Token::Colon = self.peek_next() {
-            self.primary()?
+
+    fn constraint(&mut self) -> Result<Constraint, EconError> {
+        let start = self.current;
+        let is_error = self.check(Token::ErrorMacro);
+        let name = if is_error { "error" } else { "constraint" };
+        self.eat();
+
+        if is_error {
+            self.consume(Token::LeftCurl, "Expected '{' after '@!'.".to_string())?;
         } else {
-            self.equality()?
+            self.consume(Token::LeftCurl, "Expected '{' after '@'.".to_string())?;
+        }
+
+        let ty = match self.peek().clone() {
+            Token::Str(s) => s,
+            Token::Nil => "nil".to_string(),
+            _ if is_error => { return self.error("Error Macro preprocessor Error.".to_string()); }
+            _ => { return self.error("Constraint Macro preprocessor Error.".to_string()); }
         };
-        self.check_val_with_constraint(val)
-    }
-    
-    fn array_value(&mut self) -> Result<EconValue, String> {
-        Ok(self.val_expression()?)
+        self.eat();
+        self.consume(Token::Comma, "Expected ',' after Constraint Type.".to_string())?;
+
+        let name = format!("{} {}", ty, name);
+        let param = self.reference(&name)?;
+        self.consume(Token::Arrow, format!("{}: Expect '=>' after reference.", name))?;
+        let condition = self.val_expression()?;
+        self.consume(Token::Comma, format!("{}: Expect ',' after condition.", name))?;
+        let value = self.val_expression()?;
+        self.consume(Token::RightCurl, format!("{}: Expect '}}' after value.", name))?;
+
+        Ok(Constraint { ty, param, condition, value, is_error, span: self.span_from(start) })
     }
-    
-    fn array(&mut self) -> Result<EconValue, String> {
-        let mut result = vec!();
-        
-        while !self.check(Token::RightBracket) && !self.at_end() {
-            let val = self.array_value()?;
-            result.push(val);
-            if !self.check(Token::RightBracket) {
-                self.consume(Token::Comma, "Expect ',' or ']'.".to_string())?;  
+
+    fn block(&mut self) -> Result<ExprKind, EconError> {
+        let mut items = vec!();
+
+        while !self.check(Token::RightCurl) && !self.at_end() {
+            let start = self.current;
+
+            if let Err(e) = self.block_entry(&mut items) {
+                self.recover_from(e, start, Token::RightCurl)?;
             }
         }
-        
-        self.consume(Token::RightBracket, "Expect ']' after array.".to_string())?;
-        Ok(EconValue::Arr(result))
-    }
-    
-    fn key(&mut self) -> Result<(String, EconValue), String> {
-        let v_key = self.val_expression()?;
-            
-        if let EconValue::Str(s) = v_key {
-            self.consume(Token::Colon, "Expected ':' after Key identifier".to_string())?;
-            Ok((s.clone(), self.val_expression()?))
-        } else {
-            self.error(format!("Expected Key got: {}.", v_key))
+        if let Err(e) = self.consume(Token::RightCurl, "Expect '}' to terminate Object definition.".to_string()) {
+            self.recover_from(e, self.current, Token::RightCurl)?;
+            self.match_single(Token::RightCurl);
         }
+        Ok(ExprKind::Object(Object { items }))
     }
 
-    fn check_val_with_constraint(&mut self, input: EconValue) -> Result<EconValue, String>{
-        match input {
-            EconValue::Str(s) => {
-                if !self.in_constraint {
-                    let mut str_to_use : Option<EconValue> = None;
-
-                    for depth in (0..=self.depth).rev() {
-                        let constr_vec = if let Some(cv) = self.constraints[depth as usize].get("string") {
-                            Some(cv.clone())
-                        } else {
-                            None
-                        };
-        
-                        if let Some(cv) = constr_vec {
-                            self.in_constraint = true;
-                            for cst in cv.iter() {
-                                let name = if cst.1 {
-                                    "string error"
-                                } else {
-                                    "string constraint"
-                                };
-                                    
-                                let return_to = self.current;
-                                self.current = cst.0;
-        
-                                let temp_var = self.create_temp_var(name)?;
-                                if let EconValue::Str(ref tv) = &temp_var.0 {
-                                    if let Some(ntu) = &str_to_use {
-                                        self.locals[self.depth as usize].insert(tv.clone(), 
-                                            ntu.clone()
-                                        );
-                                    } else {
-                                        self.locals[self.depth as usize].insert(tv.clone(), 
-                                            EconValue::Str(s.to_string())
-                                        );
-                                    }
-                                }
-                                
-                                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference.", name))?;
-                                let condition = self.val_expression()?;
-                                self.consume(Token::Comma, format!("{}: Expect ',' after condition.", name))?;
-                                let val = self.val_expression()?;
-                                
-                                match condition {
-                                    EconValue::Bool(true) => {
-                                        if cst.1 {
-                                            match val {
-                                                EconValue::Str(s) => {
-                                                    return self.error(format!("{}", s));
-                                                }
-                                                _ => return self.error(format!("{}", val))
-                                            }
-                                        }
-                                        str_to_use = Some(val);
-                                    }
-                                    EconValue::Bool(false) => { }
-                                    _ => {
-                                        return self.error(format!("{}: condition must be boolean.", name));
-                                    }
-                                }
-    
-                                self.restore_temp_var(temp_var);
-                                self.current = return_to;
-                            }
-                            self.in_constraint = false;
-                        }
-                    }
-                    
-                    if let Some(value_to_use) = str_to_use {
-                        Ok(value_to_use)
-                    } else {
-                        Ok(EconValue::Str(s))
-                    }
-                } else {
-                    Ok(EconValue::Str(s))
-                }
-            }
-            EconValue::Bool(b) => {
-                if !self.in_constraint {
-                    let mut bool_to_use : Option<EconValue> = None;
-
-                    for depth in (0..=self.depth).rev() {
-                        let constr_vec = if let Some(cv) = self.constraints[depth as usize].get("bool") {
-                            Some(cv.clone())
-                        } else {
-                            None
-                        };
-        
-                        if let Some(cv) = constr_vec {
-                            self.in_constraint = true;
-                            for cst in cv.iter() {
-                                let name = if cst.1 {
-                                    "bool error"
-                                } else {
-                                    "bool constraint"
-                                };
-                                let return_to = self.current;
-                                self.current = cst.0;
-        
-                                let temp_var = self.create_temp_var(name)?;
-                                if let EconValue::Str(ref s) = &temp_var.0 {
-                                    if let Some(ntu) = &bool_to_use {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            ntu.clone()
-                                        );
-                                    } else {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            EconValue::Bool(b)
-                                        );
-                                    }
-                                }
-                                
-                                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference.", name))?;
-                                let condition = self.val_expression()?;
-                                self.consume(Token::Comma, format!("{}: Expect ',' after condition.", name))?;
-                                let val = self.val_expression()?;
-                                
-                                match condition {
-                                    EconValue::Bool(true) => {
-                                        if cst.1 {
-                                            match val {
-                                                EconValue::Str(s) => {
-                                                    return self.error(format!("{}", s));
-                                                }
-                                                _ => return self.error(format!("{}", val))
-                                            }
-                                            
-                                        }
-                                        bool_to_use = Some(val);
-                                    }
-                                    EconValue::Bool(false) => { }
-                                    _ => {
-                                        return self.error(format!("{}: condition must be boolean.", name));
-                                    }
-                                }
-    
-                                self.restore_temp_var(temp_var);
-                                self.current = return_to;
-                            }
-                            self.in_constraint = false;
-                        }
-                    }
-                    
-                    if let Some(value_to_use) = bool_to_use {
-                        Ok(value_to_use)
-                    } else {
-                        Ok(EconValue::Bool(b))
-                    }
-                } else {
-                    Ok(EconValue::Bool(b))
-                }
+    fn block_entry(&mut self, items: &mut Vec<Item>) -> Result<(), EconError> {
+        while let Token::ConstraintMacro | Token::ErrorMacro = self.peek() {
+            let constraint = self.constraint()?;
+            items.push(Item::Constraint(constraint));
+        }
+        if self.check(Token::RightCurl) {
+            return Ok(());
+        }
+
+        match (self.peek(), self.peek_next()) {
+            (Token::Str(word), Token::Str(_)) if word == "let" => {
+                let binding = self.binding()?;
+                items.push(Item::Let(binding));
             }
-            EconValue::Num(n) => {
-                if !self.in_constraint {
-                    let mut num_to_use : Option<EconValue> = None;
-
-                    for depth in (0..=self.depth).rev() {
-                        let constr_vec = if let Some(cv) = self.constraints[depth as usize].get("number") {
-                            Some(cv.clone())
-                        } else {
-                            None
-                        };
-        
-                        if let Some(cv) = constr_vec {
-                            self.in_constraint = true;
-                            for cst in cv.iter() {
-                                let name = if cst.1 {
-                                    "number error"
-                                } else {
-                                    "number constraint"
-                                };
-                                let return_to = self.current;
-                                self.current = cst.0;
-        
-                                let temp_var = self.create_temp_var(name)?;
-                                if let EconValue::Str(ref s) = &temp_var.0 {
-                                    if let Some(ntu) = &num_to_use {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            ntu.clone()
-                                        );
-                                    } else {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            EconValue::Num(n)
-                                        );
-                                    }
-                                }
-                                
-                                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference.", name))?;
-                                let condition = self.val_expression()?;
-                                self.consume(Token::Comma, format!("{}: Expect ',' after condition.", name))?;
-                                let val = self.val_expression()?;
-                                
-                                match condition {
-                                    EconValue::Bool(true) => {
-                                        if cst.1 {
-                                            match val {
-                                                EconValue::Str(s) => {
-                                                    return self.error(format!("{}", s));
-                                                }
-                                                _ => return self.error(format!("{}", val))
-                                            }
-                                            
-                                        }
-                                        num_to_use = Some(val);
-                                    }
-                                    EconValue::Bool(false) => { }
-                                    _ => {
-                                        return self.error(format!("{}: condition must be boolean.", name));
-                                    }
-                                }
-    
-                                self.restore_temp_var(temp_var);
-                                self.current = return_to;
-                            }
-                            self.in_constraint = false;
-                        }
-                    }
-                    
-                    if let Some(value_to_use) = num_to_use {
-                        Ok(value_to_use)
-                    } else {
-                        Ok(EconValue::Num(n))
-                    }
-                } else {
-                    Ok(EconValue::Num(n))
-                }
+            (Token::Str(word), Token::Str(_)) if word == "fn" => {
+                let binding = self.function()?;
+                items.push(Item::Let(binding));
             }
-            EconValue::Nil => {
-                if !self.in_constraint {
-                    let mut nil_to_use : Option<EconValue> = None;
-
-                    for depth in (0..=self.depth).rev() {
-                        let constr_vec = if let Some(cv) = self.constraints[depth as usize].get("nil") {
-                            Some(cv.clone())
-                        } else {
-                            None
-                        };
-        
-                        if let Some(cv) = constr_vec {
-                            self.in_constraint = true;
-                            for cst in cv.iter() {
-                                let name = if cst.1 {
-                                    "nil error"
-                                } else {
-                                    "nil constraint"
-                                };
-                                let return_to = self.current;
-                                self.current = cst.0;
-        
-                                let temp_var = self.create_temp_var(name)?;
-                                if let EconValue::Str(ref s) = &temp_var.0 {
-                                    if let Some(ntu) = &nil_to_use {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            ntu.clone()
-                                        );
-                                    } else {
-                                        self.locals[self.depth as usize].insert(s.clone(), 
-                                            EconValue::Nil
-                                        );
-                                    }
-                                }
-                                
-                                self.consume(Token::Arrow, format!("{}: Expect '=>' after reference.", name))?;
-                                let condition = self.val_expression()?;
-                                self.consume(Token::Comma, format!("{}: Expect ',' after condition.", name))?;
-                                let val = self.val_expression()?;
-                                
-                                match condition {
-                                    EconValue::Nil => {
-                                        if cst.1 {
-                                            match val {
-                                                EconValue::Str(s) => {
-                                                    return self.error(format!("{}", s));
-                                                }
-                                                _ => return self.error(format!("{}", val))
-                                            }
-                                            
-                                        }
-                                        nil_to_use = Some(val);
-                                    }
-                                    _ => {
-                                        return self.error(format!("{}: condition must be boolean.", name));
-                                    }
-                                }
-    
-                                self.restore_temp_var(temp_var);
-                                self.current = return_to;
-                            }
-                            self.in_constraint = false;
-                        }
-                    }
-                    
-                    if let Some(value_to_use) = nil_to_use {
-                        Ok(value_to_use)
-                    } else {
-                        Ok(EconValue::Nil)
-                    }
-                } else {
-                    Ok(EconValue::Nil)
-                }
+            _ => {
+                let key = self.val_expression()?;
+                self.consume(Token::Colon, "Expected ':' after Key identifier".to_string())?;
+                let value = self.val_expression()?;
+                items.push(Item::Entry(Entry { key, value }));
             }
-            _ => { Ok(input) }
         }
-    }
 
-    fn constraint_pre_process(&mut self) -> Result<(), String> {
-        loop {
-            match self.peek().clone() {
-                Token::ConstraintMacro => {
-                    self.eat();
-                    self.consume(Token::LeftCurl, "Expected '{' after '@'.".to_string())?;
-
-                    match self.peek().clone() {
-                        Token::Str(s) => {
-                            self.eat();
-                            self.consume(Token::Comma, "Expected ',' after Constraint Type.".to_string())?;
-                            let start_of = self.current;
-                            
-                            if let Some(v) = self.constraints[self.depth as usize].get_mut(s.as_str()) {
-                                v.push((start_of, false));
-                            } else {
-                                self.constraints[self.depth as usize].insert(s.clone(), vec!((start_of, false)));
-                            }
-                            
-                            loop {
-                                if self.match_single(Token::RightCurl) {
-                                    break;
-                                }
-                                self.eat();
-                            }
-
-                            if self.check(Token::ConstraintMacro) || self.check(Token::ErrorMacro) {
-                                continue;    
-                            }
-                        }
-                        Token::Nil => {
-                            self.eat();
-                            self.consume(Token::Comma, "Expected ',' after Constraint Type.".to_string())?;
-                            let start_of = self.current;
-                            
-                            if let Some(v) = self.constraints[self.depth as usize].get_mut("nil") {
-                                v.push((start_of, false));
-                            } else {
-                                self.constraints[self.depth as usize].insert("nil".to_string(), vec!((start_of, false)));
-                            }
-                            
-                            loop {
-                                if self.match_single(Token::RightCurl) {
-                                    break;
-                                }
-                                self.eat();
-                            }
-
-                            if self.check(Token::ConstraintMacro) || self.check(Token::ErrorMacro) {
-                                continue;    
-                            }
-                        }
-                        _ => { return self.error("Constraint Macro preprocessor Error.".to_string()); }
-                    }
-                }
-                Token::ErrorMacro => {
-                    self.eat();
-                    self.consume(Token::LeftCurl, "Expected '{' after '@!'.".to_string())?;
-
-                    match self.peek().clone() {
-                        Token::Str(s) => {
-                            self.eat();                        
-                            self.consume(Token::Comma, "Expected ',' after Constraint Type.".to_string())?;
-                            let start_of = self.current;
-                            
-                            if let Some(v) = self.constraints[self.depth as usize].get_mut(s.as_str()) {
-                                v.push((start_of, true));
-                            } else {
-                                self.constraints[self.depth as usize].insert(s.clone(), vec!((start_of, true)));
-                            }
-                            
-                            loop {
-                                if self.match_single(Token::RightCurl) {
-                                    break;
-                                }
-                                self.eat();
-                            }
-
-                            if self.check(Token::ConstraintMacro) || self.check(Token::ErrorMacro) {
-                                continue;    
-                            }
-                        }
-                        Token::Nil => {
-                            self.eat();                        
-                            self.consume(Token::Comma, "Expected ',' after Constraint Type.".to_string())?;
-                            let start_of = self.current;
-                            
-                            if let Some(v) = self.constraints[self.depth as usize].get_mut("nil") {
-                                v.push((start_of, true));
-                            } else {
-                                self.constraints[self.depth as usize].insert("nil".to_string(), vec!((start_of, true)));
-                            }
-                            
-                            loop {
-                                if self.match_single(Token::RightCurl) {
-                                    break;
-                                }
-                                self.eat();
-                            }
-
-                            if self.check(Token::ConstraintMacro) || self.check(Token::ErrorMacro) {
-                                continue;    
-                            }
-                        }
-                        _ => { return self.error("Error Macro preprocessor Error.".to_string());  }
-                    }
-                   
-                }
-                _ => { break; }
-            }
+        if !self.check(Token::RightCurl) {
+            self.consume(Token::Comma, format!("Expect ',' or '}}' got {:?}.", self.peek()))?;
         }
-        
         Ok(())
     }
-    
-    fn expression(&mut self) -> Result<(String, EconValue), String> {
-        self.key()
+
+    /// The name after `let` or `fn`, as the key of the binding.
+    fn binding_name(&mut self, word: &str) -> Result<(String, Expr), EconError> {
+        self.eat();
+        let start = self.current;
+        let name = self.reference(word)?;
+        Ok((name.clone(), Expr::new(ExprKind::Str(name), self.span_from(start))))
     }
-    
-    // fn object(&mut self) -> Result<EconValue, String> {
-    //     if self.match_single(Token::LeftCurl) {
-    //         self.locals.push(HashMap::new());
-    //         self.constraints.push(HashMap::new());
-    //         self.depth += 1;
-    //         self.block()
-    //     } else {
-    //         self.error(format!("Expect '{{' to begin Object definition got {:?}.", self.peek()))
-    //     }
-    // }
-    
-    fn block(&mut self) -> Result<EconValue, String> {
-        let mut result = EconObj::new();
-        
-        while !self.check(Token::RightCurl) && !self.at_end() {
-            self.constraint_pre_process()?;
 
-            let key_val = self.expression()?;
-            if let Some(_) = result.data.get(&key_val.0) {
-                return self.error("Duplicate Key.".to_string());
-            } else {
-                result.data.insert(key_val.0.clone(), key_val.1.clone());
-                self.locals[self.depth as usize].insert(key_val.0, key_val.1);
-            }
-            if !self.check(Token::RightCurl) {
-                self.consume(Token::Comma, format!("Expect ',' or '}}' got {:?}.", self.peek()))?;  
+    /// `let name = value` where value may be a lambda, `let double = x => $x * 2`.
+    fn binding(&mut self) -> Result<Entry, EconError> {
+        let (name, key) = self.binding_name("let")?;
+        self.consume(Token::Assign, format!("{}: Expect '=' after name.", name))?;
+        let value = self.argument(&name)?;
+        Ok(Entry { key, value })
+    }
+
+    /// `fn name(x, y) => body`, the same as `let name = |x, y| => body`.
+    fn function(&mut self) -> Result<Entry, EconError> {
+        let (name, key) = self.binding_name("fn")?;
+        let start = self.current;
+        self.consume(Token::LeftParen, format!("{}: Expect '(' after name.", name))?;
+
+        let mut params = vec!();
+        while !self.check(Token::RightParen) && !self.at_end() {
+            params.push(self.reference(&name)?);
+            if !self.check(Token::RightParen) {
+                self.consume(Token::Comma, format!("{}: Expect ',' after reference.", name))?;
             }
         }
-        self.consume(Token::RightCurl, "Expect '}' to terminate Object definition.".to_string())?;
-        Ok(EconValue::Obj(result))
+        self.consume(Token::RightParen, format!("{}: Expect ')' after references.", name))?;
+        self.consume(Token::Arrow, format!("{}: Expect '=>' after ')'.", name))?;
+
+        let body = self.val_expression()?;
+        let value = Expr::new(ExprKind::Lambda(Lambda { params, body: Box::new(body) }), self.span_from(start));
+        Ok(Entry { key, value })
     }
 
-    pub fn parse(&mut self, lexer: &mut EconLexer, debug: bool) -> Result<EconValue, String> {
-        if debug {  
-            println!("----Src----"); 
-            println!("{}", lexer.source);
-            println!("----Lex----");
+    fn lex(&mut self, lexer: &mut EconLexer) -> Result<(), EconError> {
+        if let Some(max) = self.options.max_source_len {
+            if self.source.len() > max {
+                let diagnostic = Diagnostic::new(
+                    format!("Source is {} bytes, more than the maximum of {}.", self.source.len(), max),
+                    Span::default()
+                ).in_file(self.options.source_name.clone());
+                return Err(EconError::Lex(diagnostic));
+            }
         }
-        
-        let now = Instant::now();
 
         loop {
             match lexer.scan() {
                 Ok(TokenData {
                     token: Token::EOF,
-                    line: _,
+                    ..
                 }) => {
                     break;
                 }
                 Ok(TokenData {
                     token: Token::Macro(mac),
-                    line: _,
+                    ..
                 }) => {
                     for i in mac.into_iter() {
-                        if debug { println!("{}", i); }
+                        self.options.emit(Trace::Tokens, || i.to_string());
                         self.tokens.push(i);
                     }
                 }
                 Ok(token) => {
-                    if debug { println!("{}", token); }
+                    self.options.emit(Trace::Tokens, || token.to_string());
                     self.tokens.push(token);
                 }
-                Err(msg) => {
-                    if msg == "Macro" {
-                        continue;
+                Err(e) => {
+                    if !self.recover {
+                        return Err(e);
                     }
-                    return Err(format!("{}", msg));
+                    self.errors.push(e);
+                    lexer.synchronize();
                 }
             }
         }
-        
-        
-        if debug { 
-            println!("[Completed in {} ms]", now.elapsed().as_millis());
-            println!("----Parse----"); 
+
+        Ok(())
+    }
+
+    /// Lex and parse the source into an expression tree without evaluating it.
+    /// The tree can be handed to an [`EconEvaluator`] any number of times.
+    pub fn parse_ast(&mut self, lexer: &mut EconLexer) -> Result<Expr, EconError> {
+        self.lex(lexer)?;
+        self.val_expression()
+    }
+
+    /// Lex, parse and evaluate, tracing each stage as configured in the parser's [`EconOptions`].
+    pub fn parse(&mut self, lexer: &mut EconLexer) -> Result<EconValue, EconError> {
+        let result = self.parse_traced(lexer);
+
+        if let Err(e) = &result {
+            self.options.emit(Trace::Errors, || e.to_string());
         }
 
-        let result = match self.val_expression() {
-            Ok(value) => {
-                if debug { 
-                    println!("[Completed in {} ms]", now.elapsed().as_millis()); 
-                    println!("{}", &value);
-                }
+        result
+    }
+
+    fn parse_traced(&mut self, lexer: &mut EconLexer) -> Result<EconValue, EconError> {
+        let options = self.options.clone();
+        options.emit(Trace::Source, || format!("----Src----\n{}", lexer.source));
+        options.emit(Trace::Tokens, || "----Lex----".to_string());
+
+        let now = Instant::now();
+
+        self.lex(lexer)?;
+
+        options.emit(Trace::Timings, || format!("[Lexed in {} ms]", now.elapsed().as_millis()));
+
+        let ast = self.val_expression()?;
+
+        options.emit(Trace::Timings, || format!("[Parsed in {} ms]", now.elapsed().as_millis()));
+        options.emit(Trace::Ast, || format!("----Parse----\n{:#?}", ast));
+
+        let value = EconEvaluator::with_options(self.source.clone(), &options).evaluate(&ast)?;
+
+        options.emit(Trace::Timings, || format!("[Evaluated in {} ms]", now.elapsed().as_millis()));
+        options.emit(Trace::Value, || format!("----Eval----\n{}", value));
+
+        Ok(value)
+    }
+
+    /// Parse without stopping at the first problem. Lexing, parsing and error macro failures are collected
+    /// while the parser skips to the next ',', '}' or ']' and carries on. Returns whatever could be
+    /// evaluated along with every error found, in source order.
+    pub fn parse_recovering(&mut self, lexer: &mut EconLexer) -> (EconValue, Vec<EconError>) {
+        self.recover = true;
+        self.errors.clear();
+
+        let _ = self.lex(lexer);
+
+        let value = match self.val_expression() {
+            Ok(ast) => {
+                let (value, errors) = EconEvaluator::with_options(self.source.clone(), &self.options).evaluate_recovering(&ast);
+                self.errors.extend(errors);
                 value
             }
             Err(e) => {
-                return Err(e)
+                self.errors.push(e);
+                EconValue::Nil
             }
         };
 
-        Ok(result)
+        let mut errors = std::mem::take(&mut self.errors);
+        errors.sort_by_key(|e| e.span().map(|s| s.start));
+        (value, errors)
     }
 }
@@ -0,0 +1,250 @@
+use crate::{error::EconError, value::EconValue};
+
+/// One step of a path: `.key`, `["key"]` or `[index]`.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl EconValue {
+    /// Look up a Json Pointer, RFC 6901, e.g. `/a/b/c/2`. `""` is the value itself, `~1` stands for `/`
+    /// and `~0` for `~` inside a key.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let obj = Econ::from("{ a: { b: [1, 2, 3] }, \"x/y\": true }");
+    /// assert_eq!(Some(3f64), obj.pointer("/a/b/2").and_then(|v| v.as_f64()));
+    /// assert_eq!(Some(true), obj.pointer("/x~1y").and_then(|v| v.as_bool()));
+    /// assert!(obj.pointer("/a/c").is_none());
+    /// ```
+    pub fn pointer(&self, pointer: &str) -> Option<&EconValue> {
+        let mut current = self;
+        for token in pointer_tokens(pointer)? {
+            current = match current {
+                EconValue::Obj(o) => o.data.get(&token)?,
+                EconValue::Arr(a) => a.get(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Mutable [`pointer`](EconValue::pointer).
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut EconValue> {
+        let mut current = self;
+        for token in pointer_tokens(pointer)? {
+            current = match current {
+                EconValue::Obj(o) => o.data.get_mut(&token)?,
+                EconValue::Arr(a) => a.get_mut(pointer_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Look up a path written the way the `.` and `[]` selectors are in Econ source, e.g. `a.b.c[2]`.
+    /// Keys that aren't plain words can be quoted, `a["x.y"]`, and a number after `.` indexes an array
+    /// like `[]` does. Unlike indexing, a missing key or index is an `EconError::Path` naming the
+    /// segment that failed instead of `nil`.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, error::EconError};
+    ///
+    /// let obj = Econ::from("{ a: { b: { c: [1, 2, 3] } } }");
+    /// assert_eq!(Some(3f64), obj.path("a.b.c[2]").unwrap().as_f64());
+    ///
+    /// let Err(EconError::Path { path, message }) = obj.path("a.x.c") else { panic!() };
+    /// assert_eq!("a.x", path);
+    /// assert_eq!("No key \"x\".", message);
+    /// ```
+    pub fn path(&self, path: &str) -> Result<&EconValue, EconError> {
+        let mut current = self;
+        for (segment, end) in segments(path)? {
+            current = match (current, segment.on(current)) {
+                (EconValue::Obj(o), Segment::Key(k)) => o.data.get(&k).ok_or_else(|| missing_key(&k, &path[..end]))?,
+                (EconValue::Arr(a), Segment::Index(i)) => a.get(i).ok_or_else(|| missing_index(i, a.len(), &path[..end]))?,
+                (current, segment) => return Err(mismatch(current, &segment, &path[..end])),
+            };
+        }
+        Ok(current)
+    }
+
+    /// Mutable [`path`](EconValue::path).
+    pub fn path_mut(&mut self, path: &str) -> Result<&mut EconValue, EconError> {
+        let mut current = self;
+        for (segment, end) in segments(path)? {
+            let segment = segment.on(current);
+            current = match (current, segment) {
+                (EconValue::Obj(o), Segment::Key(k)) => o.data.get_mut(&k).ok_or_else(|| missing_key(&k, &path[..end]))?,
+                (EconValue::Arr(a), Segment::Index(i)) => {
+                    let len = a.len();
+                    a.get_mut(i).ok_or_else(|| missing_index(i, len, &path[..end]))?
+                }
+                (current, segment) => return Err(mismatch(current, &segment, &path[..end])),
+            };
+        }
+        Ok(current)
+    }
+}
+
+impl Segment {
+    /// A number after `.` indexes an array, like it does in source.
+    fn on(self, value: &EconValue) -> Segment {
+        match (value, self) {
+            (EconValue::Arr(_), Segment::Key(k)) if is_index(&k) => match k.parse() {
+                Ok(i) => Segment::Index(i),
+                Err(_) => Segment::Key(k),
+            },
+            (_, segment) => segment,
+        }
+    }
+}
+
+/// `path` followed by `key`, quoted if it couldn't be read back as a plain key.
+pub(crate) fn key(path: &str, key: &str) -> String {
+    if key.is_empty() || key.contains(['.', '[']) {
+        format!("{}[\"{}\"]", path, key.replace('\\', "\\\\").replace('"', "\\\""))
+    } else if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// `path` followed by `[index]`.
+pub(crate) fn index(path: &str, index: usize) -> String {
+    format!("{}[{}]", path, index)
+}
+
+/// Unescaped reference tokens of `pointer`, `None` if it isn't empty and doesn't start with `/`.
+fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(vec!());
+    }
+
+    let rest = pointer.strip_prefix('/')?;
+    Some(rest.split('/').map(|t| t.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// Array index in a pointer: digits with no leading zero.
+fn pointer_index(token: &str) -> Option<usize> {
+    if !is_index(token) || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+fn is_index(token: &str) -> bool {
+    !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Split `path` into segments, each paired with the byte offset it ends at so errors can quote the
+/// path up to the segment that failed.
+fn segments(path: &str) -> Result<Vec<(Segment, usize)>, EconError> {
+    let bytes = path.as_bytes();
+    let mut result = vec!();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'[' {
+            let (segment, end) = bracket(path, i)?;
+            result.push((segment, end));
+            i = end;
+            continue;
+        }
+
+        if bytes[i] == b'.' {
+            if result.is_empty() {
+                return Err(syntax(&path[..=i], "Expect key before '.'."));
+            }
+            i += 1;
+        }
+
+        let start = i;
+        while i < bytes.len() && bytes[i] != b'.' && bytes[i] != b'[' {
+            i += 1;
+        }
+        if start == i {
+            return Err(syntax(&path[..i], "Expect key after '.'."));
+        }
+        result.push((Segment::Key(path[start..i].to_string()), i));
+    }
+
+    Ok(result)
+}
+
+/// `[index]` or `["key"]` starting at `start`.
+fn bracket(path: &str, start: usize) -> Result<(Segment, usize), EconError> {
+    let rest = &path[start + 1..];
+
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let mut key = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c)) => key.push(c),
+                    None => break,
+                },
+                '"' => {
+                    let end = start + 2 + i + 1;
+                    if path[end..].starts_with(']') {
+                        return Ok((Segment::Key(key), end + 1));
+                    }
+                    return Err(syntax(&path[..end], "Expect ']' after key."));
+                }
+                c => key.push(c),
+            }
+        }
+        return Err(syntax(path, "Unterminated key."));
+    }
+
+    match rest.find(']') {
+        Some(close) => {
+            let end = start + 1 + close + 1;
+            let index = &rest[..close];
+            if !is_index(index) {
+                return Err(syntax(&path[..end], "Expect index or quoted key inside '[]'."));
+            }
+            index
+                .parse()
+                .map(|i| (Segment::Index(i), end))
+                .map_err(|_| syntax(&path[..end], "Index is too large."))
+        }
+        None => Err(syntax(path, "Expect ']' after index.")),
+    }
+}
+
+fn syntax(path: &str, message: &str) -> EconError {
+    EconError::Path {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn missing_key(key: &str, path: &str) -> EconError {
+    EconError::Path {
+        path: path.to_string(),
+        message: format!("No key \"{}\".", key),
+    }
+}
+
+fn missing_index(index: usize, len: usize, path: &str) -> EconError {
+    EconError::Path {
+        path: path.to_string(),
+        message: format!("Index {} is out of bounds for an array of {}.", index, len),
+    }
+}
+
+fn mismatch(value: &EconValue, segment: &Segment, path: &str) -> EconError {
+    let message = match segment {
+        Segment::Key(k) => format!("Expect object for key \"{}\" got {}.", k, value.type_name()),
+        Segment::Index(i) => format!("Expect array for index [{}] got {}.", i, value.type_name()),
+    };
+    EconError::Path {
+        path: path.to_string(),
+        message,
+    }
+}
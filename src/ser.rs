@@ -0,0 +1,607 @@
+use std::fmt;
+
+use serde::ser::{self, Impossible, Serialize, SerializeMap as _, Serializer};
+
+use crate::{error::EconError, object::EconObj, value::EconValue};
+
+/// Convert any `Serialize` type into an [`EconValue`]. Structs and maps become objects, sequences and tuples
+/// become arrays, `None` and `()` become `nil`. Integers become an `Int`, unless a `u64` is too big for one,
+/// and floats a `Num`.
+/// # Example
+/// ```rust
+/// use econ_rs::{econ::Econ, to_value};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Defaults {
+///     port: u16,
+///     hosts: Vec<&'static str>,
+/// }
+///
+/// let value = to_value(&Defaults { port: 80, hosts: vec!["a.local"] }).unwrap();
+/// assert_eq!(80f64, value["port"].value::<f64>());
+/// assert_eq!("a.local", value["hosts"][0].value::<String>());
+/// ```
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<EconValue, EconError> {
+    value.serialize(ValueSerializer)
+}
+
+impl ser::Error for EconError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        EconError::Serialize {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl Serialize for EconValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EconValue::Nil => serializer.serialize_unit(),
+            EconValue::Num(n) => serializer.serialize_f64(*n),
+            EconValue::Int(n) => serializer.serialize_i64(*n),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => serializer.serialize_newtype_struct(DECIMAL_TOKEN, &DecimalNumber(n)),
+            EconValue::Bool(b) => serializer.serialize_bool(*b),
+            EconValue::Str(s) => serializer.serialize_str(s),
+            EconValue::Arr(a) => serializer.collect_seq(a),
+            EconValue::Obj(o) => o.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for EconObj {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.data.len()))?;
+        for (k, v) in &self.data {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+/// Name of the newtype a `Dec` is wrapped in so [`to_value`] can read it back as a `Dec`. Other serializers
+/// only see the number inside.
+#[cfg(feature = "decimal")]
+const DECIMAL_TOKEN: &str = "$econ_rs::Dec";
+
+/// How a decimal is handed to serde: an integer if it is one, a float if the float's shortest form reads back
+/// as the same decimal, which is how `rust_decimal` reads floats, and its digits as text otherwise so none are lost.
+#[cfg(feature = "decimal")]
+pub(crate) enum DecimalForm {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+#[cfg(feature = "decimal")]
+pub(crate) fn decimal_form(n: &rust_decimal::Decimal) -> DecimalForm {
+    use rust_decimal::prelude::ToPrimitive;
+
+    if let Some(i) = n.fract().is_zero().then(|| n.to_i64()).flatten() {
+        return DecimalForm::Int(i);
+    }
+    match n.to_f64() {
+        Some(f) if f.to_string().parse::<rust_decimal::Decimal>().ok() == Some(*n) => DecimalForm::Float(f),
+        _ => DecimalForm::Text(n.to_string()),
+    }
+}
+
+#[cfg(feature = "decimal")]
+struct DecimalNumber<'a>(&'a rust_decimal::Decimal);
+
+#[cfg(feature = "decimal")]
+impl Serialize for DecimalNumber<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match decimal_form(self.0) {
+            DecimalForm::Int(i) => serializer.serialize_i64(i),
+            DecimalForm::Float(f) => serializer.serialize_f64(f),
+            DecimalForm::Text(digits) => serializer.serialize_str(&digits),
+        }
+    }
+}
+
+/// The serializer behind [`to_value`].
+pub struct ValueSerializer;
+
+fn variant(name: &str, value: EconValue) -> EconValue {
+    let mut obj = EconObj::new();
+    obj.data.insert(name.to_string(), value);
+    EconValue::Obj(obj)
+}
+
+impl Serializer for ValueSerializer {
+    type Ok = EconValue;
+    type Error = EconError;
+    type SerializeSeq = SerializeArr;
+    type SerializeTuple = SerializeArr;
+    type SerializeTupleStruct = SerializeArr;
+    type SerializeTupleVariant = SerializeArr;
+    type SerializeMap = SerializeObj;
+    type SerializeStruct = SerializeObj;
+    type SerializeStructVariant = SerializeObj;
+
+    fn serialize_bool(self, v: bool) -> Result<EconValue, EconError> {
+        Ok(EconValue::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<EconValue, EconError> {
+        Ok(EconValue::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<EconValue, EconError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<EconValue, EconError> {
+        Ok(EconValue::from(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<EconValue, EconError> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<EconValue, EconError> {
+        Ok(EconValue::Num(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<EconValue, EconError> {
+        Ok(EconValue::Str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<EconValue, EconError> {
+        Ok(EconValue::Str(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<EconValue, EconError> {
+        Ok(EconValue::Arr(v.iter().map(|b| EconValue::Int(*b as i64)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<EconValue, EconError> {
+        Ok(EconValue::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<EconValue, EconError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<EconValue, EconError> {
+        Ok(EconValue::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<EconValue, EconError> {
+        Ok(EconValue::Nil)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<EconValue, EconError> {
+        Ok(EconValue::Str(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<EconValue, EconError> {
+        let value = value.serialize(self)?;
+        match name {
+            #[cfg(feature = "decimal")]
+            DECIMAL_TOKEN => decimal(value),
+            _ => Ok(value),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<EconValue, EconError> {
+        let value = value.serialize(self).map_err(|e| e.at(variant))?;
+        Ok(self::variant(variant, value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArr, EconError> {
+        Ok(SerializeArr {
+            variant: None,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeArr, EconError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeArr, EconError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeArr, EconError> {
+        Ok(SerializeArr {
+            variant: Some(variant),
+            items: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeObj, EconError> {
+        Ok(SerializeObj {
+            variant: None,
+            obj: EconObj::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeObj, EconError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeObj, EconError> {
+        Ok(SerializeObj {
+            variant: Some(variant),
+            obj: EconObj::new(),
+            key: None,
+        })
+    }
+}
+
+/// Collects the elements of a sequence, tuple or tuple variant.
+pub struct SerializeArr {
+    variant: Option<&'static str>,
+    items: Vec<EconValue>,
+}
+
+impl SerializeArr {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        let value = value
+            .serialize(ValueSerializer)
+            .map_err(|e| e.at(&format!("[{}]", self.items.len())))?;
+        self.items.push(value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<EconValue, EconError> {
+        let arr = EconValue::Arr(self.items);
+        match self.variant {
+            Some(name) => Ok(variant(name, arr)),
+            None => Ok(arr),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArr {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeArr {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArr {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArr {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        let variant = self.variant.unwrap_or_default();
+        self.push(value).map_err(|e| e.at(variant))
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+/// Collects the entries of a map, struct or struct variant.
+pub struct SerializeObj {
+    variant: Option<&'static str>,
+    obj: EconObj,
+    key: Option<String>,
+}
+
+impl SerializeObj {
+    fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), EconError> {
+        let value = value.serialize(ValueSerializer).map_err(|e| e.at(&key))?;
+        self.obj.data.insert(key, value);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<EconValue, EconError> {
+        let obj = EconValue::Obj(self.obj);
+        match self.variant {
+            Some(name) => Ok(variant(name, obj)),
+            None => Ok(obj),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeObj {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), EconError> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), EconError> {
+        match self.key.take() {
+            Some(key) => self.insert(key, value),
+            None => Err(ser::Error::custom("value serialized before key")),
+        }
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeObj {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), EconError> {
+        self.insert(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeObj {
+    type Ok = EconValue;
+    type Error = EconError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), EconError> {
+        let variant = self.variant.unwrap_or_default();
+        self.insert(key.to_string(), value).map_err(|e| e.at(variant))
+    }
+
+    fn end(self) -> Result<EconValue, EconError> {
+        self.finish()
+    }
+}
+
+/// The `Dec` a [`DecimalNumber`] was written as.
+#[cfg(feature = "decimal")]
+fn decimal(value: EconValue) -> Result<EconValue, EconError> {
+    let n = match &value {
+        EconValue::Int(i) => Some(rust_decimal::Decimal::from(*i)),
+        EconValue::Num(f) => f.to_string().parse().ok(),
+        EconValue::Str(digits) => rust_decimal::Decimal::from_str_exact(digits).ok(),
+        _ => None,
+    };
+    n.map(EconValue::Dec).ok_or_else(|| ser::Error::custom(format!("invalid decimal {}", value)))
+}
+
+/// Object keys are strings. Numbers, bools and chars are written out, anything else is an error.
+struct KeySerializer;
+
+impl KeySerializer {
+    fn invalid() -> EconError {
+        ser::Error::custom("Key must be a string, number or bool.")
+    }
+}
+
+impl Serializer for KeySerializer {
+    type Ok = String;
+    type Error = EconError;
+    type SerializeSeq = Impossible<String, EconError>;
+    type SerializeTuple = Impossible<String, EconError>;
+    type SerializeTupleStruct = Impossible<String, EconError>;
+    type SerializeTupleVariant = Impossible<String, EconError>;
+    type SerializeMap = Impossible<String, EconError>;
+    type SerializeStruct = Impossible<String, EconError>;
+    type SerializeStructVariant = Impossible<String, EconError>;
+
+    fn serialize_bool(self, v: bool) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String, EconError> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_none(self) -> Result<String, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, EconError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<String, EconError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<String, EconError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, EconError> {
+        Err(Self::invalid())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, EconError> {
+        Err(Self::invalid())
+    }
+}
@@ -1,11 +1,22 @@
 use std::{fmt, ops::{Index, IndexMut}};
 
-use crate::object::{Access, EconObj};
+use indexmap::map::Entry;
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::ToPrimitive;
 
-#[derive(Debug, Clone)]
+use crate::{error::EconError, json::{JsonOptions, JsonWriter}, object::{Access, EconObj}};
+
+/// Compares structurally: numbers by value whatever their representation, `NaN` equal to itself and
+/// objects regardless of key order. Sorts `nil < bool < number < string < array < object`.
+#[derive(Debug, Clone, Default)]
 pub enum EconValue {
+    #[default]
     Nil,
     Num(f64),
+    Int(i64),
+    /// Exact decimal, produced for number literals with a fraction when the `decimal` feature is enabled
+    #[cfg(feature = "decimal")]
+    Dec(rust_decimal::Decimal),
     Bool(bool),
     Str(String),
     Arr(Vec<EconValue>),
@@ -20,7 +31,7 @@ impl From<bool> for EconValue {
 impl From<&EconValue> for bool {
     fn from(item: &EconValue) -> Self {
         if let EconValue::Bool(v) = item {
-            *v as bool
+            *v
         } else {
             false
         }
@@ -48,152 +59,178 @@ impl From<&str> for EconValue {
     }
 }
 
+impl From<&String> for EconValue {
+    fn from(item: &String) -> Self {
+        EconValue::Str(item.clone())
+    }
+}
+
 impl From<i8> for EconValue {
     fn from(item: i8) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for i8 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as i8
-        } else {
-            0i8
+        match item {
+            EconValue::Int(v) => *v as i8,
+            EconValue::Num(v) => *v as i8,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_i8().unwrap_or_default(),
+            _ => 0i8
         }
     }
 }
 
 impl From<i16> for EconValue {
     fn from(item: i16) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for i16 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as i16
-        } else {
-            0i16
+        match item {
+            EconValue::Int(v) => *v as i16,
+            EconValue::Num(v) => *v as i16,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_i16().unwrap_or_default(),
+            _ => 0i16
         }
     }
 }
 
 impl From<i32> for EconValue {
     fn from(item: i32) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for i32 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as i32
-        } else {
-            0i32
+        match item {
+            EconValue::Int(v) => *v as i32,
+            EconValue::Num(v) => *v as i32,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_i32().unwrap_or_default(),
+            _ => 0i32
         }
     }
 }
 
 impl From<i64> for EconValue {
     fn from(item: i64) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item)
     }
 }
 impl From<&EconValue> for i64 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as i64
-        } else {
-            0i64
+        match item {
+            EconValue::Int(v) => *v,
+            EconValue::Num(v) => *v as i64,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_i64().unwrap_or_default(),
+            _ => 0i64
         }
     }
 }
 
 impl From<isize> for EconValue {
     fn from(item: isize) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for isize {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as isize
-        } else {
-            0isize
+        match item {
+            EconValue::Int(v) => *v as isize,
+            EconValue::Num(v) => *v as isize,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_isize().unwrap_or_default(),
+            _ => 0isize
         }
     }
 }
 
 impl From<u8> for EconValue {
     fn from(item: u8) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for u8 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as u8
-        } else {
-            0u8
+        match item {
+            EconValue::Int(v) => *v as u8,
+            EconValue::Num(v) => *v as u8,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_u8().unwrap_or_default(),
+            _ => 0u8
         }
     }
 }
 
 impl From<u16> for EconValue {
     fn from(item: u16) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for u16 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as u16
-        } else {
-            0u16
+        match item {
+            EconValue::Int(v) => *v as u16,
+            EconValue::Num(v) => *v as u16,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_u16().unwrap_or_default(),
+            _ => 0u16
         }
     }
 }
 
 impl From<u32> for EconValue {
     fn from(item: u32) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Int(item as i64)
     }
 }
 impl From<&EconValue> for u32 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as u32
-        } else {
-            0u32
+        match item {
+            EconValue::Int(v) => *v as u32,
+            EconValue::Num(v) => *v as u32,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_u32().unwrap_or_default(),
+            _ => 0u32
         }
     }
 }
 
 impl From<u64> for EconValue {
     fn from(item: u64) -> Self {
-        EconValue::Num(item as f64)
+        i64::try_from(item).map(EconValue::Int).unwrap_or(EconValue::Num(item as f64))
     }
 }
 impl From<&EconValue> for u64 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as u64
-        } else {
-            0u64
+        match item {
+            EconValue::Int(v) => *v as u64,
+            EconValue::Num(v) => *v as u64,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_u64().unwrap_or_default(),
+            _ => 0u64
         }
     }
 }
 
 impl From<usize> for EconValue {
     fn from(item: usize) -> Self {
-        EconValue::Num(item as f64)
+        i64::try_from(item).map(EconValue::Int).unwrap_or(EconValue::Num(item as f64))
     }
 }
 impl From<&EconValue> for usize {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as usize
-        } else {
-            0usize
+        match item {
+            EconValue::Int(v) => *v as usize,
+            EconValue::Num(v) => *v as usize,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_usize().unwrap_or_default(),
+            _ => 0usize
         }
     }
 }
@@ -205,25 +242,168 @@ impl From<f32> for EconValue {
 }
 impl From<&EconValue> for f32 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as f32
-        } else {
-            0f32
+        match item {
+            EconValue::Int(v) => *v as f32,
+            EconValue::Num(v) => *v as f32,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_f32().unwrap_or_default(),
+            _ => 0f32
         }
     }
 }
 
 impl From<f64> for EconValue {
     fn from(item: f64) -> Self {
-        EconValue::Num(item as f64)
+        EconValue::Num(item)
     }
 }
 impl From<&EconValue> for f64 {
     fn from(item: &EconValue) -> Self {
-        if let EconValue::Num(v) = item {
-            *v as f64
-        } else {
-            0f64
+        match item {
+            EconValue::Int(v) => *v as f64,
+            EconValue::Num(v) => *v,
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(v) => v.to_f64().unwrap_or_default(),
+            _ => 0f64
+        }
+    }
+}
+
+impl From<EconObj> for EconValue {
+    fn from(item: EconObj) -> Self {
+        EconValue::Obj(item)
+    }
+}
+
+impl<T: Into<EconValue>> From<Vec<T>> for EconValue {
+    fn from(item: Vec<T>) -> Self {
+        EconValue::Arr(item.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` is `nil`.
+impl<T: Into<EconValue>> From<Option<T>> for EconValue {
+    fn from(item: Option<T>) -> Self {
+        item.map_or(EconValue::Nil, Into::into)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl From<rust_decimal::Decimal> for EconValue {
+    fn from(item: rust_decimal::Decimal) -> Self {
+        EconValue::Dec(item)
+    }
+}
+#[cfg(feature = "decimal")]
+impl From<&EconValue> for rust_decimal::Decimal {
+    fn from(item: &EconValue) -> Self {
+        match item {
+            EconValue::Dec(v) => *v,
+            EconValue::Int(v) => (*v).into(),
+            EconValue::Num(v) => rust_decimal::Decimal::try_from(*v).unwrap_or_default(),
+            _ => rust_decimal::Decimal::ZERO
+        }
+    }
+}
+
+fn edit_error(path: &str, message: String) -> EconError {
+    EconError::Path {
+        path: path.to_string(),
+        message,
+    }
+}
+
+fn conversion_error(message: String) -> EconError {
+    EconError::Deserialize {
+        path: String::new(),
+        message,
+    }
+}
+
+macro_rules! try_from_int {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<EconValue> for $ty {
+                type Error = EconError;
+
+                /// Fails unless `item` is a whole number that fits.
+                fn try_from(item: EconValue) -> Result<Self, EconError> {
+                    let whole = match &item {
+                        EconValue::Int(v) => Some(*v as i128),
+                        EconValue::Num(v) if v.fract() == 0.0 => Some(*v as i128),
+                        #[cfg(feature = "decimal")]
+                        EconValue::Dec(v) if v.fract().is_zero() => v.to_i128(),
+                        EconValue::Num(_) => None,
+                        #[cfg(feature = "decimal")]
+                        EconValue::Dec(_) => None,
+                        _ => {
+                            return Err(conversion_error(format!("Expected {} got {}.", stringify!($ty), item.type_name())));
+                        }
+                    };
+
+                    match whole {
+                        Some(v) => <$ty>::try_from(v)
+                            .map_err(|_| conversion_error(format!("{} is out of range for {}.", item, stringify!($ty)))),
+                        None => Err(conversion_error(format!("Expected {} got {}.", stringify!($ty), item))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+try_from_int!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl TryFrom<EconValue> for f64 {
+    type Error = EconError;
+
+    fn try_from(item: EconValue) -> Result<Self, EconError> {
+        item.as_f64()
+            .ok_or_else(|| conversion_error(format!("Expected f64 got {}.", item.type_name())))
+    }
+}
+
+impl TryFrom<EconValue> for f32 {
+    type Error = EconError;
+
+    fn try_from(item: EconValue) -> Result<Self, EconError> {
+        item.as_f64()
+            .map(|v| v as f32)
+            .ok_or_else(|| conversion_error(format!("Expected f32 got {}.", item.type_name())))
+    }
+}
+
+impl TryFrom<EconValue> for bool {
+    type Error = EconError;
+
+    fn try_from(item: EconValue) -> Result<Self, EconError> {
+        item.as_bool()
+            .ok_or_else(|| conversion_error(format!("Expected bool got {}.", item.type_name())))
+    }
+}
+
+impl TryFrom<EconValue> for String {
+    type Error = EconError;
+
+    fn try_from(item: EconValue) -> Result<Self, EconError> {
+        match item {
+            EconValue::Str(s) => Ok(s),
+            _ => Err(conversion_error(format!("Expected string got {}.", item.type_name()))),
+        }
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl TryFrom<EconValue> for rust_decimal::Decimal {
+    type Error = EconError;
+
+    fn try_from(item: EconValue) -> Result<Self, EconError> {
+        match item {
+            EconValue::Dec(v) => Ok(v),
+            EconValue::Int(v) => Ok(v.into()),
+            EconValue::Num(v) => rust_decimal::Decimal::try_from(v)
+                .map_err(|_| conversion_error(format!("{} is out of range for Decimal.", v))),
+            _ => Err(conversion_error(format!("Expected Decimal got {}.", item.type_name()))),
         }
     }
 }
@@ -231,10 +411,184 @@ impl From<&EconValue> for f64 {
 impl EconValue {
     const NIL: EconValue = EconValue::Nil;
     
+    /// Convert to `T` the way `as` does, giving `0`, `false` or `"Undefined"` when the value is something else.
+    /// See [`try_value`](EconValue::try_value) to check the conversion instead.
     pub fn value<T: for<'a> std::convert::From<&'a EconValue>>(&self) -> T {
         let res : T = self.into();
         res
-    } 
+    }
+
+    /// Convert to `T` or fail with an ```EconError::Deserialize``` saying why. Numbers must be whole and in
+    /// range to become an integer, and a missing key is `nil` so it fails too. Backed by the `TryFrom<EconValue>`
+    /// impls, which take the value by itself.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::econ::Econ;
+    ///
+    /// let obj = Econ::from("{ port: 8080, big: 70000, ratio: 0.5 }");
+    /// assert_eq!(8080, obj["port"].try_value::<u16>().unwrap());
+    /// assert_eq!("70000 is out of range for u16.", obj["big"].try_value::<u16>().unwrap_err().message());
+    /// assert!(obj["ratio"].try_value::<u16>().is_err());
+    /// assert!(obj["missing"].try_value::<u16>().is_err());
+    /// ```
+    pub fn try_value<T: TryFrom<EconValue, Error = EconError>>(&self) -> Result<T, EconError> {
+        T::try_from(self.clone())
+    }
+
+    /// `"nil"`, `"number"`, `"bool"`, `"string"`, `"array"` or `"object"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            EconValue::Nil => "nil",
+            EconValue::Num(_) | EconValue::Int(_) => "number",
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(_) => "number",
+            EconValue::Bool(_) => "bool",
+            EconValue::Str(_) => "string",
+            EconValue::Arr(_) => "array",
+            EconValue::Obj(_) => "object",
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, EconValue::Nil)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            EconValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Any number, converted to `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            EconValue::Num(n) => Some(*n),
+            EconValue::Int(n) => Some(*n as f64),
+            #[cfg(feature = "decimal")]
+            EconValue::Dec(n) => n.to_f64(),
+            _ => None,
+        }
+    }
+
+    /// An integer, or a whole number that fits in an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.try_value().ok()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            EconValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<EconValue>> {
+        match self {
+            EconValue::Arr(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&EconObj> {
+        match self {
+            EconValue::Obj(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<EconValue>> {
+        match self {
+            EconValue::Arr(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut EconObj> {
+        match self {
+            EconValue::Obj(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Like [`as_array_mut`](EconValue::as_array_mut) but `nil` becomes an empty array first.
+    pub fn array_mut(&mut self) -> Option<&mut Vec<EconValue>> {
+        if self.is_nil() {
+            *self = EconValue::Arr(vec!());
+        }
+        self.as_array_mut()
+    }
+
+    /// Like [`as_object_mut`](EconValue::as_object_mut) but `nil` becomes an empty object first.
+    pub fn object_mut(&mut self) -> Option<&mut EconObj> {
+        if self.is_nil() {
+            *self = EconValue::Obj(EconObj::new());
+        }
+        self.as_object_mut()
+    }
+
+    /// Set `key`, returning what it held before. `nil` becomes an empty object first.
+    /// # Example
+    /// ```rust
+    /// use econ_rs::{econ::Econ, value::EconValue};
+    ///
+    /// let mut obj = Econ::from("{ server: { host: \"a.local\" } }");
+    /// obj["server"].insert("port", 8080).unwrap();
+    /// obj["client"].push("retry").unwrap();
+    /// *obj["limits"].entry("max").unwrap().or_insert(EconValue::from(10)) = EconValue::from(20);
+    /// assert_eq!(r#"{"server":{"host":"a.local","port":8080},"client":["retry"],"limits":{"max":20}}"#,
+    ///     obj.to_json(&econ_rs::json::JsonOptions::new().compact()).unwrap());
+    ///
+    /// assert!(obj["server"]["host"].insert("x", 1).is_err());
+    /// ```
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<EconValue>) -> Result<Option<EconValue>, EconError> {
+        let key = key.into();
+        let type_name = self.type_name();
+        match self.object_mut() {
+            Some(o) => Ok(o.insert(key, value)),
+            None => Err(edit_error(&key, format!("Expect object to insert into got {}.", type_name))),
+        }
+    }
+
+    /// Append to an array. `nil` becomes an empty array first.
+    pub fn push(&mut self, value: impl Into<EconValue>) -> Result<(), EconError> {
+        let type_name = self.type_name();
+        match self.array_mut() {
+            Some(a) => {
+                a.push(value.into());
+                Ok(())
+            }
+            None => Err(edit_error("", format!("Expect array to push onto got {}.", type_name))),
+        }
+    }
+
+    /// Remove `key` from an object, `None` if it isn't there or this isn't an object.
+    pub fn remove(&mut self, key: &str) -> Option<EconValue> {
+        self.as_object_mut()?.remove(key)
+    }
+
+    /// Entry for `key`. `nil` becomes an empty object first, `None` for anything else.
+    pub fn entry(&mut self, key: impl Into<String>) -> Option<Entry<'_, String, EconValue>> {
+        Some(self.object_mut()?.entry(key))
+    }
+
+    /// The value at `key`, set to the result of `f` first if there isn't one. `nil` becomes an empty
+    /// object first, `None` for anything else.
+    pub fn get_or_insert_with(&mut self, key: impl Into<String>, f: impl FnOnce() -> EconValue) -> Option<&mut EconValue> {
+        Some(self.object_mut()?.get_or_insert_with(key, f))
+    }
+
+    /// Keep only the keys of an object for which `f` returns true. Anything else is left alone.
+    pub fn retain(&mut self, f: impl FnMut(&str, &mut EconValue) -> bool) {
+        if let Some(o) = self.as_object_mut() {
+            o.retain(f);
+        }
+    }
+
+    /// Move the value out, leaving `nil` in its place.
+    pub fn take(&mut self) -> EconValue {
+        std::mem::take(self)
+    }
 }
 
 impl Access<&str> for EconValue {
@@ -285,57 +639,58 @@ impl Access<usize> for EconValue {
 impl Index<&str> for EconValue {
     type Output = EconValue;
     fn index<'a>(&'a self, i: &str) -> &'a EconValue {
-        &self.get(i)
+        self.get(i)
     }
 }
 
 impl Index<usize> for EconValue {
     type Output = EconValue;
-    fn index<'a>(&'a self, i: usize) -> &'a EconValue {
-        &self.get(i)
+    fn index(&self, i: usize) -> &EconValue {
+        self.get(i)
     }
 }
 
+/// A missing key is inserted as `nil` and anything that isn't an object is replaced by an empty one, so
+/// `value["a"]["b"] = x` builds the objects on the way. Use [`entry`](EconValue::entry) or
+/// [`path_mut`](EconValue::path_mut) to leave other values alone.
 impl IndexMut<&str> for EconValue {
     fn index_mut<'a>(&'a mut self, i: &str) -> &'a mut EconValue {
-        match self.get_mut(i) {
-            Some(v) => v,
-            None => panic!("Cannot index mut None"),
+        if self.as_object().is_none() {
+            *self = EconValue::Obj(EconObj::new());
+        }
+        match self {
+            EconValue::Obj(o) => o.get_or_insert_with(i, || EconValue::Nil),
+            _ => unreachable!(),
         }
     }
 }
 
+/// Anything that isn't an array is replaced by an empty one and an array too short for `i` is padded with
+/// `nil`, so `value[3] = x` always lands. Use [`path_mut`](EconValue::path_mut) to leave other values alone.
 impl IndexMut<usize> for EconValue {
-    fn index_mut<'a>(&'a mut self, i: usize) -> &'a mut EconValue {
-        match self.get_mut(i) {
-            Some(v) => v,
-            None => panic!("Cannot index mut None"),
+    fn index_mut(&mut self, i: usize) -> &mut EconValue {
+        if self.as_array().is_none() {
+            *self = EconValue::Arr(vec!());
+        }
+        match self {
+            EconValue::Arr(a) => {
+                if a.len() <= i {
+                    a.resize(i + 1, EconValue::Nil);
+                }
+                &mut a[i]
+            }
+            _ => unreachable!(),
         }
     }
 }
 
+/// Tab indented Json with keys in source order. `inf` and `NaN` are written as `null`, use
+/// [`to_json`](EconValue::to_json) to choose otherwise.
 impl fmt::Display for EconValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            EconValue::Obj(o) => {
-                write!(f, "{}", o.get_string_from_obj(o, 0))
-            }
-            EconValue::Arr(a) => {
-                let b = EconObj::new();
-                write!(f, "[\n{}", b.get_string_from_arr(a, 0))
-            }
-            EconValue::Bool(b) => {
-                write!(f, "{}", b)
-            }
-            EconValue::Num(n) => {
-                write!(f, "{}", n)
-            }
-            EconValue::Str(s) => {
-                write!(f, "\"{}\"", s)
-            }
-            EconValue::Nil => {
-                write!(f, "nil")
-            }
-        }
+        let options = JsonOptions::display();
+        let mut writer = JsonWriter::new(&options);
+        let _ = writer.value(self, 0);
+        f.write_str(&writer.out)
     }
 }